@@ -0,0 +1,39 @@
+//! Benchmarks for transpiling programs with large vocabularies, such as generated fact files.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Makes a distinct proper name from a number, such as 'Zbcd', as names can't contain digits.
+fn name(mut number: usize) -> String {
+    let mut name = String::from("Z");
+    loop {
+        name.push((b'a' + (number % 26) as u8) as char);
+        number /= 26;
+        if number == 0 {
+            break name;
+        }
+    }
+}
+
+/// Generates a program with the given number of facts, each of which introduces a new name.
+fn facts(count: usize) -> String {
+    (0..count)
+        .map(|i| format!("{} is the parent of {}.\n", name(i + 1), name(i)))
+        .collect()
+}
+
+fn transpile(c: &mut Criterion) {
+    let mut group = c.benchmark_group("transpile facts");
+    group.sample_size(10);
+
+    for count in [1_000, 10_000, 40_000] {
+        let source = facts(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &source, |b, source| {
+            b.iter(|| co_log::transpile(source.clone(), None).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, transpile);
+criterion_main!(benches);
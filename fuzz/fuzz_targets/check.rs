@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Checking any source must report its errors without panicking
+fuzz_target!(|source: &str| {
+    let _ = co_log::check(source);
+});
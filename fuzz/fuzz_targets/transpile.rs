@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Transpiling any source must either fail with a parse error or generate valid Prolog
+fuzz_target!(|source: &str| {
+    if let Ok((prolog, _, _, _)) = co_log::transpile(source.to_string(), None) {
+        if let Err(err) = co_log::check_prolog(&prolog) {
+            panic!("generated invalid Prolog ({err}):\n{prolog}");
+        }
+    }
+});
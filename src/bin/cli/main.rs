@@ -4,7 +4,7 @@ use ctrlc; // A library to handle ctrl-c signals
 use directories::ProjectDirs; // A library to access data folders on any platform
 use scrawl; // A library to open the user's text editor
 use std::{
-    env, fs,
+    fs,
     io::{self, BufRead, Write},
 };
 use tokio; // An asynchronous runtime
@@ -22,9 +22,35 @@ struct Args {
     /// The file to query
     file: Option<String>,
 
-    /// Whether to simply transpile then exit
+    /// Whether to simply transpile then exit, printing the generated Prolog
     #[arg(short)]
     dry_run: bool,
+
+    /// Whether to name identifiers in the generated Prolog after their Co-log names
+    #[arg(short, long)]
+    readable: bool,
+
+    /// Whether to log the steps of transpiling and querying to stderr
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+/// The engine used to answer queries, which is SWI-Prolog unless the crate is built without it.
+#[cfg(feature = "swipl")]
+type Backend = co_log::Session;
+#[cfg(not(feature = "swipl"))]
+type Backend = co_log::Evaluator;
+
+/// Starts the engine used to answer queries.
+#[cfg(feature = "swipl")]
+fn start_backend() -> Result<Backend, co_log::SessionError> {
+    co_log::Session::new()
+}
+
+/// Starts the engine used to answer queries.
+#[cfg(not(feature = "swipl"))]
+fn start_backend() -> Result<Backend, std::convert::Infallible> {
+    Ok(co_log::Evaluator::new())
 }
 
 /// Get the user's input from the command line.
@@ -197,9 +223,8 @@ fn query_file(file_path: Option<String>) {
 
     let colog = fs::read_to_string(file_to_query).unwrap();
 
-    // Transpile the Co-log to Prolog, exiting if there is an error
-    let (pl, identifiers) = match co_log::transpile(colog, None) {
-        Ok((pl, _, identifiers)) => (pl, identifiers),
+    let mut session = match start_backend() {
+        Ok(session) => session,
         Err(err) => {
             eprintln!("Error: {err}");
             wait_for_input();
@@ -207,56 +232,67 @@ fn query_file(file_path: Option<String>) {
         }
     };
 
-    // Get the temp file location
-    let mut tmp_location = env::current_exe().expect("failed to get location of executable");
-    tmp_location.pop();
-    tmp_location.push("temp.pl");
-
-    // Save the Prolog to a file
-    fs::write(&tmp_location, pl).unwrap();
-
-    /* This code is from my attempt at communicating with Prolog. It is unfinished.
-    let context = co_log::start_prolog(tmp_location.to_str().unwrap()).unwrap();
+    // Load the Co-log into Prolog, exiting if there is an error
+    if let Err(err) = session.load(&colog) {
+        // Show every mistake in the file at once, rather than just the first
+        let errors = co_log::check(&colog);
+        if errors.is_empty() {
+            print_error(&err);
+        }
+        for err in errors {
+            eprintln!("{}\n", err.render());
+        }
+        wait_for_input();
+        return;
+    }
 
     println!("Enter your queries, or enter ':exit' to finish.");
 
-    let mut input = get_user_input();
-    while &input != ":exit" {
-        let query;
-        (query, identifiers) = co_log::transpile_query(input.clone(), identifiers).unwrap();
-
-        let succeeded = co_log::query_prolog(&context, query).unwrap();
-
-        if succeeded {
-            println!("Yes");
-        } else {
-            println!("No");
+    loop {
+        print!("?- ");
+        let _ = io::stdout().flush();
+        let input = get_user_input();
+        if input.trim() == ":exit" {
+            break;
         }
 
-        input = get_user_input();
+        match session.ask(&input) {
+            Ok(solutions) => print_solutions(&solutions),
+            Err(err) => print_error(&err),
+        }
     }
+}
 
-    eprintln!("error: implementation not finished")
-    */
+/// Prints an error to stderr, showing where it is in the source if it is a parse error.
+fn print_error(err: &(dyn std::error::Error + 'static)) {
+    match err
+        .source()
+        .and_then(|source| source.downcast_ref::<co_log::ParseError>())
+    {
+        Some(err) => eprintln!("{}", err.render()),
+        None => eprintln!("Error: {err}"),
+    }
+}
 
-    println!(
-        "The generated code can be found at {}. The following table can be used to translate Prolog's responses:",
-        tmp_location.display(),
-    );
+/// Prints the solutions to a query, or 'Yes' or 'No' if the query has no variables.
+fn print_solutions(solutions: &[co_log::Solution]) {
+    if solutions.is_empty() {
+        println!("No");
+        return;
+    }
 
-    // Display the table of Co-log and Prolog names
-    println!("{:<15} | {:<15}", "Co-log name", "Prolog name");
-    println!("{}", "_".repeat(16) + "|" + &"_".repeat(16));
-    for identifier in identifiers.identifiers() {
-        if identifier.cl_name() == "eq" {
-            println!("{:<15} | {:<15}", "is", "eq");
-        } else {
-            println!(
-                "{:<15} | {:<15}",
-                identifier.cl_name(),
-                identifier.pl_name()
-            );
+    for solution in solutions {
+        if solution.is_empty() {
+            println!("Yes");
+            return;
         }
+
+        let mut bindings: Vec<_> = solution
+            .iter()
+            .map(|(name, value)| format!("{name} = {value}"))
+            .collect();
+        bindings.sort();
+        println!("{}", bindings.join(", "));
     }
 }
 
@@ -277,9 +313,30 @@ async fn main() {
 
     let args = Args::parse();
 
+    if args.verbose {
+        tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(io::stderr)
+            .init();
+    }
+
     if let Some(file) = args.file {
         if args.dry_run {
-            co_log::transpile(co_log::read_file(&file).unwrap(), None).unwrap();
+            let naming = if args.readable {
+                co_log::Naming::Readable
+            } else {
+                co_log::Naming::Numbered
+            };
+            match co_log::transpile(
+                co_log::read_file(&file).unwrap(),
+                Some(co_log::Identifiers::with_naming(naming)),
+            ) {
+                Ok((prolog, _, _, _)) => print!("{prolog}"),
+                Err(err) => {
+                    print_error(&err);
+                    std::process::exit(1);
+                }
+            }
         } else {
             query_file(Some(file));
         }
@@ -49,14 +49,17 @@ Introduction to Co-log
 
 Co-log is a human-readable syntax for predicate logic. A Co-log program is made up of several statements, all ending with a full stop.
 
-Facts are one type of statement. They represent a concrete piece of knowledge. A fact is made up of one or two literals and a relationship.
-Example facts would be 'A hamster is an animal.' or 'John is the brother of Jack.'
+Facts are one type of statement. They represent a concrete piece of knowledge. A fact is made up of a relationship and one or more literals.
+Example facts would be 'A hamster is an animal.', 'John is the brother of Jack.' or 'Alice is the sender of a letter to Bob.'
+A relationship can also be a verb, as in 'Alice likes Bob.' or 'Alice gave the book to Bob.'
 
 The other type of statement is rules. A rule is a condition, where the relationship on the left hand side is applied if the right hand side is true.
 Example rules would be 'X is a mammal if X is an animal and X is warm-blooded.' or 'X is the brother of Y if X is the sibling of Y and X is male.'
+A rule can also be written with 'all', 'every' or 'each', as in 'All hamsters are mammals.' or 'Every parent of X is an ancestor of X.'
 The right hand side of a rule can contain references to any facts or rules defined in the program.
 Note that variables in rules must be in full caps.
 
 Co-log also has queries. These are questions, and end with a question mark.
 Example queries would be 'Is a hamster a mammal?' or 'Who is the brother of Jack?'.
+A query can also ask whether anything matches, how many things match, or for a list of them, as in 'Is there anyone who likes Bob?', 'How many siblings does Jack have?' or 'List all mammals.'
 ";
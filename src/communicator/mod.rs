@@ -1,109 +1,245 @@
+use std::ops::Range;
+
 use swipl::prelude::*;
+use tracing::{debug, debug_span, trace, Span};
 //use swipl_fli;
 
-/// Starts an instance of Prolog, loading the file at 'source'.
-pub fn start_prolog(source: &str) -> PrologResult<Context<ActivatedEngine>> {
-    let mut activation_attempts = 0_u8;
-    let activation = loop {
-        match initialize_swipl() {
-            None => {
-                if activation_attempts < 10 {
-                    activation_attempts += 1;
-                } else {
-                    panic!("failed to initialise Prolog");
-                }
-            }
-            Some(activation) => break activation,
-        }
-    };
+use crate::{
+    transpiler::{self, Identifiers, SourceMap},
+    Solution,
+};
 
-    let context: Context<_> = activation.into();
+mod session;
 
-    let consult = pred!(consult/1);
+pub use session::{Session, SessionError};
 
-    let location = crate::remove_path_prefix(source);
-    let term = term! { context: #location }?;
+/// Starts an instance of Prolog, loading the clauses in 'source_map', as generated by 'transpile' from the Co-log in 'source'.
+/// Returns an error if SWI-Prolog can't be initialised, such as when it has already been initialised in this process.
+pub fn start_prolog(
+    source: &str,
+    source_map: &SourceMap,
+) -> Result<Context<'static, ActivatedEngine<'static>>, SessionError> {
+    let activation = initialize_swipl().ok_or(SessionError::Uninitialised)?;
+
+    let context: Context<_> = activation.into();
+    debug!("initialised Prolog");
 
-    context.call_once(consult, [&term])?;
+    load_prolog(&context, source, source_map.clauses())?;
 
     Ok(context)
 }
 
-/// Queries Prolog with the given query.
-/// Returns true if Prolog found at least one solution, and false otherwise.
-pub fn query_prolog(
+/// Loads the builtins of the prelude, and then the given clauses, such as those from 'SourceMap::clauses' for the Co-log in 'source', into the user module.
+/// Each clause is asserted with the byte range of its statement, so that an exception raised by a clause is linked to that statement.
+pub fn load_prolog<'a>(
     context: &Context<ActivatedEngine>,
-    query: crate::transpiler::Query,
-) -> PrologResult<bool> {
-    let mut terms = Vec::new();
-    let term;
-    let term_l;
-    let term_r;
-    let relationship = query.relationship.as_str();
+    source: &str,
+    clauses: impl IntoIterator<Item = (Range<usize>, &'a str)>,
+) -> Result<(), SessionError> {
+    let assert_clause = |span: Option<Range<usize>>, clause: &str| {
+        let result = context
+            .term_from_string(&format!("assertz(user:({clause}))"))
+            .and_then(|goal| context.call_once(pred!(call / 1), [&goal]));
+        result.map_err(|err| {
+            let err = SessionError::from_prolog(context, err);
+            match span {
+                Some(span) => err.in_statement(source, span),
+                None => err,
+            }
+        })
+    };
 
-    let module = Module::new("user");
+    for clause in transpiler::builtins() {
+        assert_clause(None, clause)?;
+    }
+    let mut loaded = 0;
+    for (span, clause) in clauses {
+        assert_clause(Some(span), clause)?;
+        loaded += 1;
+    }
+    debug!(clauses = loaded, "loaded Prolog");
+
+    Ok(())
+}
+
+/// Reads the Co-log name of the literal or number that a term was bound to.
+fn read_binding(term: &Term, identifiers: &Identifiers) -> PrologResult<String> {
+    let mut pl_name = term.get_atom_name(|name| name.map(str::to_string))?;
+
+    // Numbers are named the same in Prolog and Co-log, and floats always have a decimal point
+    if pl_name.is_none() {
+        pl_name = match (term.get::<i64>(), term.get::<f64>()) {
+            (Ok(integer), _) => Some(integer.to_string()),
+            (_, Ok(float)) => Some(format!("{float:?}")),
+            _ => None,
+        };
+    }
 
-    let open_query = match query.right {
-        None => {
-            let pred: CallablePredicate<1> =
-                CallablePredicate::new(Predicate::new(Functor::new(relationship, 1), module))
-                    .unwrap();
+    Ok(match pl_name {
+        Some(pl_name) => match identifiers.get_from_pl_name(&pl_name) {
+            Some(identifier) => identifier.cl_name().to_string(),
+            None => pl_name,
+        },
+        None => String::from("_"),
+    })
+}
+
+/// An iterator over the solutions to a query, which retrieves each solution from Prolog only when it is requested.
+/// The underlying Prolog query is cut when the iterator is dropped, so it can be abandoned early with e.g. 'take'.
+pub struct Solutions<'a> {
+    query: Option<Context<'a, OpenQuery>>,
+    transpiled: crate::transpiler::Query,
+    terms: Vec<Term<'a>>,
+    identifiers: &'a Identifiers,
+    span: Span,
+}
 
-            let left = query.left;
-            //term = term!{context: #left}?;
-            term = context.term_from_string(&left)?;
-            terms.push(&term);
+impl<'a> Solutions<'a> {
+    /// Translates the current bindings of the query's variables into a solution.
+    fn read_solution(&self) -> PrologResult<Solution> {
+        let mut solution = Solution::new();
+        for (name, term) in self.transpiled.arguments.iter().zip(&self.terms) {
+            if !is_variable(name) {
+                continue;
+            }
 
-            context.open(pred, [&term])
+            let cl_name = self
+                .transpiled
+                .get_variable(name)
+                .unwrap_or(name)
+                .to_string();
+            solution.insert(cl_name, read_binding(term, self.identifiers)?);
         }
-        Some(right) => {
-            let pred: CallablePredicate<2> =
-                CallablePredicate::new(Predicate::new(Functor::new(relationship, 2), module))
-                    .unwrap();
-
-            let left = query.left;
-            //term_l = term!{context: #left}?;
-            term_l = context.term_from_string(&left)?;
-            //term_r = term!{context: #right}?;
-            term_r = context.term_from_string(&right)?;
-            terms.push(&term_l);
-            terms.push(&term_r);
-
-            context.open(pred, [&term_l, &term_r])
+
+        Ok(solution)
+    }
+
+    /// Cuts the query, if it is still open.
+    fn close(&mut self) {
+        if let Some(query) = self.query.take() {
+            let _enter = self.span.enter();
+            trace!("cutting query");
+            query.cut();
         }
-    };
+    }
+}
 
-    println!("opened query");
+impl<'a> Iterator for Solutions<'a> {
+    type Item = PrologResult<Solution>;
 
-    dbg!(&terms);
+    fn next(&mut self) -> Option<Self::Item> {
+        let _enter = self.span.enter();
+        trace!("getting solution");
+        let result = self.query.as_ref()?.next_solution();
 
-    let mut next = true;
-    let mut soln = false;
-    while next {
-        println!("getting solution");
-        next = match dbg!(open_query.next_solution()) {
+        match result {
             Ok(not_last_soln) => {
-                soln = true;
-                not_last_soln
+                let solution = self.read_solution();
+                debug!(?solution, not_last_soln, "found solution");
+                if !not_last_soln {
+                    self.close();
+                }
+
+                Some(solution)
             }
-            Err(e) => match e {
-                PrologError::Failure => false,
-                PrologError::Exception => return Err(e),
-            },
-        };
-        //soln = soln || next;
-        /*dbg!(
-            terms[0].get::<String>(),
-            terms[0].get::<Vec<u8>>(),
-            terms[0].is_var(),
-            terms[0].is_atom(),
-            terms[0].is_string()
-        );*/
+            Err(PrologError::Failure) => {
+                debug!("no more solutions");
+                self.close();
+                None
+            }
+            Err(e) => {
+                debug!("Prolog raised an exception");
+                // Dropping the query closes it without cutting, leaving the exception in place
+                self.query = None;
+                Some(Err(e))
+            }
+        }
     }
+}
 
-    dbg!(&terms[0].get::<String>(), &terms[0].is_var(), soln);
+impl<'a> Drop for Solutions<'a> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
 
-    open_query.cut();
+/// Opens a query in the given Prolog module, returning an iterator over its solutions.
+fn open_solutions<'a>(
+    context: &'a Context<ActivatedEngine>,
+    module: &str,
+    query: crate::transpiler::Query,
+    identifiers: &'a Identifiers,
+) -> PrologResult<Solutions<'a>> {
+    let span = debug_span!("query", relationship = %query.relationship);
+    let entered = span.enter();
+
+    let relationship = query.relationship.as_str();
 
-    Ok(soln)
+    let terms = query
+        .arguments
+        .iter()
+        .map(|name| context.term_from_string(name))
+        .collect::<PrologResult<Vec<_>>>()?;
+
+    // Relationships can have any number of arguments, so the goal is built as a term qualified with its module and passed to call/1
+    let goal = context.new_term_ref();
+    goal.unify(Functor::new(relationship, terms.len() as u16))?;
+    for (i, term) in terms.iter().enumerate() {
+        goal.unify_arg(i + 1, term)?;
+    }
+    let qualified = context.new_term_ref();
+    qualified.unify(Functor::new(":", 2))?;
+    qualified.unify_arg(1, Atom::new(module))?;
+    qualified.unify_arg(2, &goal)?;
+
+    let pred: CallablePredicate<1> = CallablePredicate::new(Predicate::new(
+        Functor::new("call", 1),
+        Module::new("system"),
+    ))
+    .unwrap();
+    let open_query = context.open(pred, [&qualified]);
+
+    trace!(names = ?query.arguments, "opened query");
+
+    drop(entered);
+    Ok(Solutions {
+        query: Some(open_query),
+        transpiled: query,
+        terms,
+        identifiers,
+        span,
+    })
+}
+
+/// Opens a query in Prolog, returning an iterator over its solutions.
+/// The bindings of the query's variables are translated back to Co-log through 'identifiers'.
+pub fn query_solutions<'a>(
+    context: &'a Context<ActivatedEngine>,
+    query: crate::transpiler::Query,
+    identifiers: &'a Identifiers,
+) -> PrologResult<Solutions<'a>> {
+    open_solutions(context, "user", query, identifiers)
+}
+
+/// Queries Prolog with the given query.
+/// Returns every solution that Prolog found, with the bindings of the query's variables translated back to Co-log through 'identifiers'.
+/// A query with no variables returns a single empty solution if it is true, and no solutions otherwise.
+/// Queries that ask for something else, such as the number of solutions, are answered from the solutions Prolog found.
+pub fn query_prolog(
+    context: &Context<ActivatedEngine>,
+    query: crate::transpiler::Query,
+    identifiers: &Identifiers,
+) -> PrologResult<Vec<Solution>> {
+    let kind = query.kind;
+    let solutions = query_solutions(context, query, identifiers)?.collect::<PrologResult<_>>()?;
+
+    Ok(crate::answer(kind, solutions))
 }
+
+/// Checks if a Prolog name refers to a variable.
+fn is_variable(pl_name: &str) -> bool {
+    pl_name.starts_with(|c: char| c.is_uppercase() || c == '_')
+}
+
+#[cfg(test)]
+mod tests;
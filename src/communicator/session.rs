@@ -0,0 +1,426 @@
+use std::{
+    error::Error,
+    fmt,
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use swipl::prelude::*;
+use tracing::{debug, debug_span};
+
+use super::{open_solutions, Solution};
+use crate::{
+    parser::{self, ast, Location, ParseError},
+    stratification::{self, StratificationError},
+    transpiler::{self, Identifiers, Query},
+};
+
+/// The number used to name the Prolog module of the next session.
+static NEXT_SESSION: AtomicUsize = AtomicUsize::new(1);
+
+/// An error raised by a session.
+#[derive(Debug)]
+pub enum SessionError {
+    /// The Co-log source could not be parsed.
+    Parse(ParseError),
+
+    /// The Co-log source would make a relationship depend on its own negation.
+    Unstratifiable(StratificationError),
+
+    /// Prolog raised an exception, described by the given message.
+    Prolog(String),
+
+    /// Prolog raised an exception while adding a statement to the knowledge base, or while answering a query that used it.
+    /// The statement is given with its location in the Co-log source, so that the error can be reported against it.
+    Statement {
+        location: Location,
+        statement: String,
+        message: String,
+    },
+
+    /// A different Prolog engine is already active on this thread.
+    EngineActive,
+
+    /// SWI-Prolog could not be initialised.
+    Uninitialised,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Parse(err) => write!(f, "{err}"),
+            SessionError::Unstratifiable(err) => write!(f, "{err}"),
+            SessionError::Prolog(message) => write!(f, "Prolog error: {message}"),
+            SessionError::Statement {
+                location,
+                statement,
+                message,
+            } => write!(f, "{location}: Prolog error in '{statement}': {message}"),
+            SessionError::EngineActive => {
+                write!(f, "another Prolog engine is active on this thread")
+            }
+            SessionError::Uninitialised => write!(f, "failed to initialise Prolog"),
+        }
+    }
+}
+
+impl Error for SessionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SessionError::Parse(err) => Some(err),
+            SessionError::Unstratifiable(err) => Some(err),
+            SessionError::Prolog(_)
+            | SessionError::Statement { .. }
+            | SessionError::EngineActive
+            | SessionError::Uninitialised => None,
+        }
+    }
+}
+
+impl SessionError {
+    /// Converts an error returned by Prolog, describing and clearing the exception that it raised, if there is one.
+    pub(super) fn from_prolog(context: &Context<ActivatedEngine>, err: PrologError) -> Self {
+        match err {
+            PrologError::Failure => SessionError::Prolog(String::from("goal failed")),
+            PrologError::Exception => {
+                let message = context.with_exception(|exception| match exception {
+                    Some(exception) => context
+                        .string_from_term(exception)
+                        .unwrap_or_else(|_| String::from("unknown exception")),
+                    None => String::from("unknown exception"),
+                });
+                context.clear_exception();
+
+                SessionError::Prolog(message)
+            }
+        }
+    }
+
+    /// Links an exception raised by Prolog to the statement in 'source' that caused it.
+    pub(super) fn in_statement(self, source: &str, span: Range<usize>) -> Self {
+        match self {
+            SessionError::Prolog(message) => SessionError::Statement {
+                location: Location::of(source, span.start),
+                statement: source[span].to_string(),
+                message,
+            },
+            err => err,
+        }
+    }
+}
+
+impl From<ParseError> for SessionError {
+    fn from(value: ParseError) -> Self {
+        SessionError::Parse(value)
+    }
+}
+
+impl From<StratificationError> for SessionError {
+    fn from(value: StratificationError) -> Self {
+        SessionError::Unstratifiable(value)
+    }
+}
+
+/// Co-log source that has been loaded into a session, with the clause that each of its facts and rules was asserted as.
+struct Loaded {
+    source: String,
+    /// The statements that are still in the knowledge base, each with the Prolog clause it was transpiled to.
+    clauses: Vec<(ast::Stmt, String)>,
+}
+
+/// A Prolog engine with a knowledge base built from Co-log source, and the identifiers used to translate between the two.
+/// Each session keeps its clauses in its own Prolog module, so sessions do not see each other's facts and rules.
+pub struct Session {
+    engine: Engine,
+    module: String,
+    identifiers: Identifiers,
+    rules: Vec<(ast::Stmt, Location)>,
+    loaded: Vec<Loaded>,
+}
+
+impl Session {
+    /// Creates a session with an empty knowledge base, initialising SWI-Prolog if necessary.
+    /// Returns an error if SWI-Prolog can't be initialised.
+    pub fn new() -> Result<Self, SessionError> {
+        // Creating an engine would initialise SWI-Prolog too, but couldn't report a failure to do so
+        initialize_swipl_noengine();
+        if !is_swipl_initialized() {
+            return Err(SessionError::Uninitialised);
+        }
+
+        let number = NEXT_SESSION.fetch_add(1, Ordering::Relaxed);
+        let session = Session {
+            engine: Engine::new(),
+            module: format!("co_log_session_{number}"),
+            identifiers: Identifiers::new(),
+            rules: Vec::new(),
+            loaded: Vec::new(),
+        };
+
+        for clause in transpiler::builtins() {
+            session.call(&format!("assertz({}:({clause}))", session.module))?;
+        }
+        debug!(module = session.module, "created session");
+
+        Ok(session)
+    }
+
+    /// The identifiers defined by the source loaded into this session.
+    pub fn identifiers(&self) -> &Identifiers {
+        &self.identifiers
+    }
+
+    /// Activates the session's engine on this thread, and runs 'f' with it.
+    /// Any exception raised by Prolog is cleared and converted into an error.
+    fn with_context<R>(
+        &self,
+        f: impl FnOnce(&Context<ActivatedEngine>) -> PrologResult<R>,
+    ) -> Result<R, SessionError> {
+        if Engine::some_engine_active() {
+            return Err(SessionError::EngineActive);
+        }
+
+        let context: Context<_> = self.engine.activate().into();
+        f(&context).map_err(|err| SessionError::from_prolog(&context, err))
+    }
+
+    /// Calls a goal, given as Prolog source, once.
+    fn call(&self, goal: &str) -> Result<(), SessionError> {
+        self.with_context(|context| {
+            let goal = context.term_from_string(goal)?;
+            context.call_once(pred!(call / 1), [&goal])
+        })
+    }
+
+    /// Transpiles the given statements, using and extending the session's identifiers.
+    fn transpile(&self, trees: Vec<ast::Stmt>) -> (Vec<String>, Vec<Query>, Identifiers) {
+        transpiler::transpile_clauses(trees, Some(self.identifiers.clone()))
+    }
+
+    /// Finds the statement that most likely caused an exception raised while answering a query, from the predicate indicators in its message, such as `co_log_session_1:l5/2`.
+    /// A relationship defined by a statement leads to that statement, and one that isn't defined leads to the first statement that uses it.
+    pub(super) fn culprit(&self, message: &str) -> Option<(&str, Range<usize>)> {
+        let relationships: Vec<_> = indicators(message)
+            .filter_map(|(pl_name, arity)| {
+                let identifier = self.identifiers.get_from_pl_name(pl_name)?;
+                Some((identifier.cl_name(), arity))
+            })
+            .collect();
+        let statements = || {
+            self.loaded.iter().flat_map(|loaded| {
+                loaded
+                    .clauses
+                    .iter()
+                    .map(move |(statement, _)| (loaded.source.as_str(), statement))
+            })
+        };
+
+        let defines = |statement: &ast::Stmt| {
+            relationships.iter().any(|&(name, arity)| {
+                statement.relationship().name() == name && 1 + statement.right().len() == arity
+            })
+        };
+        let uses = |statement: &ast::Stmt| {
+            statement.condition().as_ref().is_some_and(|condition| {
+                relationships
+                    .iter()
+                    .any(|&(name, arity)| clause_uses(condition, name, arity))
+            })
+        };
+
+        statements()
+            .find(|&(_, statement)| defines(statement))
+            .or_else(|| statements().find(|&(_, statement)| uses(statement)))
+            .map(|(source, statement)| (source, statement.span()))
+    }
+
+    /// Asserts the clauses of statements into the session's module, linking an exception raised by one of them to its statement in 'source'.
+    /// If a clause raises an exception, the clauses already asserted are erased again, so either every clause is added or none are.
+    fn assert_all(
+        &self,
+        source: &str,
+        clauses: &[(ast::Stmt, String)],
+    ) -> Result<(), SessionError> {
+        let failure = self.with_context(|context| {
+            let mut references = Vec::new();
+            for (statement, clause) in clauses {
+                debug!(clause, "asserting clause");
+                let reference = context.new_term_ref();
+                let result = context
+                    .term_from_string(&format!("{}:({clause})", self.module))
+                    .and_then(|clause| {
+                        context.call_once(pred!(assertz / 2), [&clause, &reference])
+                    });
+
+                if let Err(err) = result {
+                    let err = SessionError::from_prolog(context, err)
+                        .in_statement(source, statement.span());
+                    debug!(erased = references.len(), "erasing asserted clauses");
+                    for reference in references.iter().rev() {
+                        context.call_once(pred!(erase / 1), [reference])?;
+                    }
+                    return Ok(Some(err));
+                }
+                references.push(reference);
+            }
+
+            Ok(None)
+        })?;
+
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Adds the facts and rules in the given Co-log source to the knowledge base.
+    /// This can be called repeatedly to build the knowledge base up incrementally.
+    /// Returns any queries found in the source, which can be passed to 'query'.
+    /// Loading is atomic: if an error is returned, such as when a relationship would depend on its own negation or Prolog rejects a clause, the knowledge base and the session's identifiers are left as they were.
+    pub fn load(&mut self, source: &str) -> Result<Vec<Query>, SessionError> {
+        let _enter = debug_span!("load", module = self.module).entered();
+        let trees = parse(source)?;
+
+        let rules = stratification::rules(source, &trees);
+        stratification::stratify(self.rules.iter().chain(&rules))?;
+
+        // Each statement that isn't a query is transpiled to one clause, in order
+        let statements: Vec<_> = trees
+            .iter()
+            .filter(|tree| !matches!(tree.kind(), ast::StmtType::Query(_)))
+            .cloned()
+            .collect();
+        let (clauses, queries, identifiers) = self.transpile(trees);
+        let clauses: Vec<_> = statements.into_iter().zip(clauses).collect();
+        self.assert_all(source, &clauses)?;
+
+        // Nothing about the session changes until every clause has been asserted
+        self.rules.extend(rules);
+        self.identifiers = identifiers;
+        self.loaded.push(Loaded {
+            source: source.to_string(),
+            clauses,
+        });
+
+        Ok(queries)
+    }
+
+    /// Removes the facts and rules in the given Co-log source from the knowledge base.
+    /// Each statement removes the clause that an equal statement was loaded as, so one that was never loaded removes nothing.
+    /// Returns the number of clauses that were removed.
+    pub fn retract(&mut self, source: &str) -> Result<usize, SessionError> {
+        let _enter = debug_span!("retract", module = self.module).entered();
+        let statements = parse(source)?
+            .into_iter()
+            .filter(|tree| !matches!(tree.kind(), ast::StmtType::Query(_)));
+
+        let mut removed = 0;
+        for statement in statements {
+            let Some((i, j)) = self.loaded.iter().enumerate().find_map(|(i, loaded)| {
+                let j = loaded
+                    .clauses
+                    .iter()
+                    .position(|(other, _)| *other == statement)?;
+                Some((i, j))
+            }) else {
+                continue;
+            };
+
+            let clause = &self.loaded[i].clauses[j].1;
+            debug!(clause, "retracting clause");
+            let retracted = self.with_context(|context| {
+                let goal =
+                    context.term_from_string(&format!("retract({}:({clause}))", self.module))?;
+                match context.call_once(pred!(call / 1), [&goal]) {
+                    Ok(()) => Ok(true),
+                    Err(PrologError::Failure) => Ok(false),
+                    Err(e) => Err(e),
+                }
+            })?;
+
+            if retracted {
+                removed += 1;
+                if let Some(k) = self.rules.iter().position(|(rule, _)| *rule == statement) {
+                    self.rules.remove(k);
+                }
+                // Errors are no longer linked to the statement once its clause is gone
+                self.loaded[i].clauses.remove(j);
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Queries the knowledge base, returning every solution to the query, or the answer it asks for such as the number of solutions.
+    /// An exception raised by Prolog is linked to the statement of the relationship that it names, if there is one.
+    pub fn query(&self, query: Query) -> Result<Vec<Solution>, SessionError> {
+        // Declaring the relationship as dynamic makes queries about unknown relationships fail instead of raising an exception
+        let arity = query.arguments.len();
+        self.call(&format!(
+            "dynamic({}:({}/{arity}))",
+            self.module, query.relationship
+        ))?;
+
+        let kind = query.kind;
+        let solutions = self
+            .with_context(|context| {
+                open_solutions(context, &self.module, query, &self.identifiers)?.collect()
+            })
+            .map_err(|err| {
+                let culprit = match &err {
+                    SessionError::Prolog(message) => self.culprit(message),
+                    _ => None,
+                };
+                match culprit {
+                    Some((source, span)) => err.in_statement(source, span),
+                    None => err,
+                }
+            })?;
+
+        Ok(crate::answer(kind, solutions))
+    }
+
+    /// Transpiles a single Co-log query and answers it from the knowledge base.
+    pub fn ask(&mut self, source: &str) -> Result<Vec<Solution>, SessionError> {
+        let (query, identifiers) =
+            crate::transpile_query(source.to_string(), Some(self.identifiers.clone()))?;
+        self.identifiers = identifiers;
+
+        self.query(query)
+    }
+}
+
+/// Finds the predicate indicators in a message from Prolog, such as `l5/2` in `existence_error(procedure,co_log_session_1:l5/2)`, as the name and arity of each predicate.
+/// The module before a name is ignored, as is any other part of the message.
+fn indicators(message: &str) -> impl Iterator<Item = (&str, usize)> {
+    let is_name = |c: char| c.is_alphanumeric() || c == '_';
+    message.match_indices('/').filter_map(move |(i, _)| {
+        let before = &message[..i];
+        let name = &before[before.trim_end_matches(is_name).len()..];
+        let after = &message[i + 1..];
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let arity = after[..digits].parse().ok()?;
+
+        name.starts_with(char::is_lowercase)
+            .then_some((name, arity))
+    })
+}
+
+/// Checks whether a clause uses the relationship with the given Co-log name and arity.
+fn clause_uses(clause: &ast::Clause, name: &str, arity: usize) -> bool {
+    match clause {
+        ast::Clause::Simple {
+            relationship,
+            right,
+            ..
+        } => relationship.name() == name && 1 + right.len() == arity,
+        ast::Clause::Operator { clauses, .. } => clauses
+            .iter()
+            .any(|clause| clause_uses(clause, name, arity)),
+    }
+}
+
+/// Parses Co-log source into statements.
+fn parse(source: &str) -> Result<Vec<ast::Stmt>, ParseError> {
+    parser::parse_source(source)
+}
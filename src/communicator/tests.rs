@@ -0,0 +1,357 @@
+use super::*;
+
+fn session(source: &str) -> Session {
+    let mut session = Session::new().unwrap();
+    session.load(source).unwrap();
+    session
+}
+
+#[test]
+fn session_fact() {
+    let mut session = session("John is the brother of Jack.");
+
+    assert_eq!(
+        session.ask("Is John the brother of Jack?").unwrap(),
+        vec![Solution::new()]
+    );
+    assert_eq!(session.ask("Is Jack the brother of John?").unwrap(), vec![]);
+}
+
+#[test]
+fn session_pronoun() {
+    let mut session = session("John is the brother of Jack. Jim is the brother of Jack.");
+
+    assert_eq!(
+        session.ask("Who is the brother of Jack?").unwrap(),
+        vec![
+            Solution::from([("who".to_string(), "john".to_string())]),
+            Solution::from([("who".to_string(), "jim".to_string())]),
+        ]
+    );
+}
+
+#[test]
+fn session_repeated_pronoun() {
+    let mut session = session("Alice is the parent of Bob.");
+
+    assert_eq!(
+        session.ask("Who is the parent of who?").unwrap(),
+        vec![Solution::from([
+            ("who".to_string(), "alice".to_string()),
+            ("who_2".to_string(), "bob".to_string()),
+        ])]
+    );
+}
+
+#[test]
+fn session_rule() {
+    let mut session = session(
+        "A hamster is an animal. A hamster is warm-blooded. X is a mammal if X is an animal and X is warm-blooded.",
+    );
+
+    assert_eq!(
+        session.ask("Is a hamster a mammal?").unwrap(),
+        vec![Solution::new()]
+    );
+}
+
+#[test]
+fn session_incremental() {
+    let mut session = session("X is a mammal if X is an animal and X is warm-blooded.");
+    assert_eq!(session.ask("Is a hamster a mammal?").unwrap(), vec![]);
+
+    session
+        .load("A hamster is an animal. A hamster is warm-blooded.")
+        .unwrap();
+    assert_eq!(
+        session.ask("Is a hamster a mammal?").unwrap(),
+        vec![Solution::new()]
+    );
+
+    assert_eq!(session.retract("A hamster is warm-blooded.").unwrap(), 1);
+    assert_eq!(session.ask("Is a hamster a mammal?").unwrap(), vec![]);
+}
+
+#[test]
+fn retract_rule() {
+    let mut session = session(
+        "A hamster is an animal. A hamster is warm-blooded. X is a mammal if X is an animal and X is warm-blooded.",
+    );
+
+    assert_eq!(session.retract("A dog is an animal.").unwrap(), 0);
+    assert_eq!(
+        session
+            .retract(
+                "A hamster is an animal. X is a mammal if X is an animal and X is warm-blooded."
+            )
+            .unwrap(),
+        2
+    );
+    assert_eq!(session.ask("Is a hamster a mammal?").unwrap(), vec![]);
+    assert_eq!(
+        session.ask("Is a hamster warm-blooded?").unwrap(),
+        vec![Solution::new()]
+    );
+
+    // The rule is no longer in the knowledge base, so retracting it again removes nothing
+    assert_eq!(
+        session
+            .retract("X is a mammal if X is an animal and X is warm-blooded.")
+            .unwrap(),
+        0
+    );
+}
+
+#[test]
+fn session_parse_error() {
+    let mut session = Session::new().unwrap();
+
+    assert!(matches!(
+        session.load("John is the brother of Jack"),
+        Err(SessionError::Parse(_))
+    ));
+}
+
+#[test]
+fn load_from_memory() {
+    let engine = Engine::new();
+    let context: Context<_> = engine.activate().into();
+
+    let source = "John is the brother of Jack.";
+    let (_, _, identifiers, source_map) = crate::transpile(source.to_string(), None).unwrap();
+    load_prolog(&context, source, source_map.clauses()).unwrap();
+
+    let (query, identifiers) =
+        crate::transpile_query("Who is the brother of Jack?".to_string(), Some(identifiers))
+            .unwrap();
+    assert_eq!(
+        query_prolog(&context, query, &identifiers).unwrap(),
+        vec![Solution::from([("who".to_string(), "john".to_string())])]
+    );
+}
+
+#[test]
+fn session_unstratifiable() {
+    let mut session = session("Ann is one. X is even if X is one and X is not odd.");
+
+    assert!(matches!(
+        session.load("X is odd if X is one and X is not even."),
+        Err(SessionError::Unstratifiable(_))
+    ));
+    assert_eq!(session.ask("Is Ann even?").unwrap(), vec![Solution::new()]);
+}
+
+#[test]
+fn session_comparison() {
+    let mut session = session(
+        "Alice is 34 years old. Bob is 17 years old. Dave is an adult. X is an adult if X is N years old and N is at least 18.",
+    );
+
+    assert_eq!(
+        session.ask("Is Bob an adult?").unwrap(),
+        Vec::<Solution>::new()
+    );
+    assert_eq!(
+        session.ask("Is Alice an adult?").unwrap(),
+        vec![Solution::new()]
+    );
+    assert_eq!(
+        session.ask("Alice is what years old?").unwrap(),
+        vec![Solution::from([("what".to_string(), "34".to_string())])]
+    );
+}
+
+#[test]
+fn session_n_ary() {
+    let mut session = session(
+        "Alice is the sender of a letter to Bob. X is the recipient of Y from Z if Z is the sender of Y to X.",
+    );
+
+    assert_eq!(
+        session
+            .ask("Is Bob the recipient of a letter from Alice?")
+            .unwrap(),
+        vec![Solution::new()]
+    );
+    assert_eq!(
+        session.ask("Who is the sender of what to Bob?").unwrap(),
+        vec![Solution::from([
+            ("who".to_string(), "alice".to_string()),
+            ("what".to_string(), "letter".to_string()),
+        ])]
+    );
+}
+
+#[test]
+fn session_actions() {
+    let mut session =
+        session("Alice likes Bob. Bob is famous. X admires Y if X likes Y and Y is famous.");
+
+    assert_eq!(
+        session.ask("Who admires Bob?").unwrap(),
+        vec![Solution::from([("who".to_string(), "alice".to_string())])]
+    );
+}
+
+#[test]
+fn session_quantified() {
+    let mut session = session("All hamsters are mammals. Sam is a hamster.");
+
+    assert_eq!(
+        session.ask("Is Sam a mammal?").unwrap(),
+        vec![Solution::new()]
+    );
+}
+
+#[test]
+fn session_query_types() {
+    let mut session = session("Bob is a child of Alice. Carol is a child of Alice.");
+
+    assert_eq!(
+        session.ask("How many children does Alice have?").unwrap(),
+        vec![Solution::from([("how many".to_string(), "2".to_string())])]
+    );
+    assert_eq!(
+        session.ask("Is there a child of Bob?").unwrap(),
+        Vec::<Solution>::new()
+    );
+}
+
+#[test]
+fn session_variable_scopes() {
+    let mut session = session("X is a friend of Y if Y is a pal of X. Alice is a pal of Bob.");
+    let identifiers = session.identifiers().clone();
+
+    assert_eq!(
+        session.ask("Who is a friend of Alice?").unwrap(),
+        vec![Solution::from([("who".to_string(), "bob".to_string())])]
+    );
+    assert_eq!(session.identifiers(), &identifiers);
+}
+
+#[test]
+fn load_error_in_statement() {
+    let engine = Engine::new();
+    let context: Context<_> = engine.activate().into();
+
+    let source = "Alice likes Bob.\nX admires Y if X likes Y.";
+    let (_, _, _, source_map) = crate::transpile(source.to_string(), None).unwrap();
+    // Prolog won't let a clause be added to a builtin predicate
+    let clauses: Vec<_> = source_map
+        .clauses()
+        .map(|(span, clause)| {
+            (
+                span,
+                clause.replace("l4(V1, V2) :-", "atom_length(V1, V2) :-"),
+            )
+        })
+        .collect();
+    let err = load_prolog(
+        &context,
+        source,
+        clauses
+            .iter()
+            .map(|(span, clause)| (span.clone(), clause.as_str())),
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, SessionError::Statement { .. }));
+    assert!(err
+        .to_string()
+        .starts_with("line 2, column 1: Prolog error in 'X admires Y if X likes Y.': "));
+}
+
+#[test]
+fn query_error_in_statement() {
+    let session = session("Alice likes Bob.\nX admires Y if X likes Y and Y is a friend of X.");
+    let statement = |message| {
+        session
+            .culprit(message)
+            .map(|(source, span)| source[span].to_string())
+    };
+
+    // An undefined relationship leads to the statement that uses it, and a defined one to the statement that defines it
+    assert_eq!(
+        statement("error(existence_error(procedure,co_log_session_1:l5/2),co_log_session_1:l5/2)"),
+        Some("X admires Y if X likes Y and Y is a friend of X.".to_string())
+    );
+    assert_eq!(
+        statement("error(type_error(integer,l2),context(co_log_session_1:l1/2,_))"),
+        Some("Alice likes Bob.".to_string())
+    );
+    // Only the name and arity of a predicate are matched, not other names in the message
+    assert_eq!(
+        statement("error(existence_error(procedure,co_log_session_1:l1/3),co_log_session_1:l1/3)"),
+        None
+    );
+    assert_eq!(statement("error(resource_error(memory),_)"), None);
+}
+
+#[test]
+fn load_is_atomic() {
+    let mut session = session("Alice likes Bob.");
+
+    // Prolog won't let a clause be added to a builtin predicate, so the clause before it is erased again
+    let source = "Bob is nice.\nX is nice if X is nice.";
+    let clauses = [
+        (0..12, "l9(l10)"),
+        (13..36, "atom_length(V1, V2) :- l9(V1)"),
+    ];
+    let err = session.assert_all(source, &clauses).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("line 2, column 1: Prolog error in 'X is nice if X is nice.': "));
+    session
+        .call(&format!("\\+ {}:l9(_)", session.module))
+        .unwrap();
+    assert_eq!(
+        session.ask("Who likes Bob?").unwrap(),
+        vec![Solution::from([("who".to_string(), "alice".to_string())])]
+    );
+}
+
+#[test]
+fn solutions_taken_lazily() {
+    let engine = Engine::new();
+    let context: Context<_> = engine.activate().into();
+
+    let source = "John is the brother of Jack. Jim is the brother of Jack.";
+    let (_, _, identifiers, source_map) = crate::transpile(source.to_string(), None).unwrap();
+    load_prolog(&context, source, source_map.clauses()).unwrap();
+    let (query, identifiers) =
+        crate::transpile_query("Who is the brother of Jack?".to_string(), Some(identifiers))
+            .unwrap();
+
+    // between(1, inf, Who) has infinitely many solutions, so it only ends because 'take' stops asking for them
+    let mut infinite = query.clone();
+    infinite.relationship = String::from("between");
+    infinite.arguments = vec![
+        String::from("1"),
+        String::from("inf"),
+        query.arguments[0].clone(),
+    ];
+    let solutions = query_solutions(&context, infinite, &identifiers)
+        .unwrap()
+        .take(3)
+        .collect::<PrologResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(
+        solutions,
+        ["1", "2", "3"].map(|n| Solution::from([("who".to_string(), n.to_string())]))
+    );
+
+    // Dropping the iterator after the first of several solutions cuts the query, so another can be opened
+    let mut solutions = query_solutions(&context, query.clone(), &identifiers).unwrap();
+    assert_eq!(
+        solutions.next().unwrap().unwrap(),
+        Solution::from([("who".to_string(), "john".to_string())])
+    );
+    drop(solutions);
+    assert_eq!(
+        query_prolog(&context, query, &identifiers).unwrap(),
+        vec![
+            Solution::from([("who".to_string(), "john".to_string())]),
+            Solution::from([("who".to_string(), "jim".to_string())]),
+        ]
+    );
+}
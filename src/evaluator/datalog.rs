@@ -0,0 +1,520 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use tracing::{debug, debug_span, trace};
+
+use super::{compare_numbers, Goal, Query, Relationship, Rule, Term};
+use crate::{parser::ast::Comparison, Solution};
+
+/// The values of the arguments of a fact.
+type Tuple = Vec<Arc<str>>;
+
+/// The facts known about a relationship, in the order that they were derived.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Relation {
+    tuples: Vec<Tuple>,
+    index: HashSet<Tuple>,
+}
+
+impl Relation {
+    /// Adds a fact to the relation.
+    /// Returns false if the fact was already known.
+    fn insert(&mut self, tuple: Tuple) -> bool {
+        if self.index.contains(&tuple) {
+            return false;
+        }
+
+        self.index.insert(tuple.clone());
+        self.tuples.push(tuple);
+        true
+    }
+}
+
+/// A single condition in a conjunctive rule body.
+#[derive(Debug, Clone, Copy)]
+enum Literal<'a> {
+    Positive(&'a Relationship, &'a [Term]),
+    Negative(&'a Relationship, &'a [Term]),
+    Compare(Comparison, &'a Term, &'a Term),
+    NotCompare(Comparison, &'a Term, &'a Term),
+}
+
+impl Literal<'_> {
+    fn is_positive(&self) -> bool {
+        matches!(self, Literal::Positive(..))
+    }
+}
+
+/// Rewrites a goal in disjunctive normal form, returning the conditions of each disjunct.
+/// Rules are only loaded for bottom-up evaluation if their bodies have at most 'MAX_DISJUNCTS' disjuncts, so the expansion stays small.
+fn disjuncts(goal: &Goal) -> Vec<Vec<Literal<'_>>> {
+    match goal {
+        Goal::Call {
+            relationship,
+            arguments,
+        } => vec![vec![Literal::Positive(relationship, arguments)]],
+        Goal::Compare(comparison, left, right) => {
+            vec![vec![Literal::Compare(*comparison, left, right)]]
+        }
+        Goal::Not(goal) => match goal.as_ref() {
+            Goal::Call {
+                relationship,
+                arguments,
+            } => vec![vec![Literal::Negative(relationship, arguments)]],
+            Goal::Compare(comparison, left, right) => {
+                vec![vec![Literal::NotCompare(*comparison, left, right)]]
+            }
+            _ => unreachable!("only simple clauses can be negated"),
+        },
+        Goal::And(goals) => goals.iter().fold(vec![Vec::new()], |left, goal| {
+            let right = disjuncts(goal);
+            left.into_iter()
+                .flat_map(|left| {
+                    right.iter().map(move |right| {
+                        let mut conjunction = left.clone();
+                        conjunction.extend_from_slice(right);
+                        conjunction
+                    })
+                })
+                .collect()
+        }),
+        Goal::Or(goals) => goals.iter().flat_map(disjuncts).collect(),
+    }
+}
+
+/// A rule whose body is a conjunction of literals.
+/// The positive literals come first, so that the others are only checked once as many variables as possible are bound.
+#[derive(Debug)]
+struct Conjunction<'a> {
+    relationship: &'a Relationship,
+    head: &'a [Term],
+    body: Vec<Literal<'a>>,
+    variables: usize,
+}
+
+impl<'a> Conjunction<'a> {
+    /// Splits a rule into a conjunction for each of the disjuncts of its body.
+    fn from_rule(relationship: &'a Relationship, rule: &'a Rule) -> Vec<Self> {
+        let bodies = match &rule.body {
+            Some(body) => disjuncts(body),
+            None => vec![Vec::new()],
+        };
+
+        bodies
+            .into_iter()
+            .map(|body| {
+                let (mut positive, negative): (Vec<_>, Vec<_>) =
+                    body.into_iter().partition(Literal::is_positive);
+                positive.extend(negative);
+
+                Conjunction {
+                    relationship,
+                    head: &rule.head,
+                    body: positive,
+                    variables: rule.variables,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The value of each variable in a rule, or None if it is unbound.
+type Bindings = Vec<Option<Arc<str>>>;
+
+/// Gets the value of a term, or None if it is an unbound variable.
+fn value(term: &Term, bindings: &Bindings) -> Option<Arc<str>> {
+    match term {
+        Term::Literal(literal) => Some(literal.clone()),
+        Term::Variable(variable) => bindings[*variable].clone(),
+    }
+}
+
+/// Binds the variables in 'arguments' to match a tuple.
+/// Returns the variables that were bound, or None if the tuple does not match, in which case no variables are left bound.
+fn match_tuple(arguments: &[Term], tuple: &Tuple, bindings: &mut Bindings) -> Option<Vec<usize>> {
+    let mut bound = Vec::new();
+    for (argument, value) in arguments.iter().zip(tuple) {
+        match argument {
+            Term::Literal(literal) if literal == value => {}
+            Term::Variable(variable) => match &bindings[*variable] {
+                Some(binding) if binding == value => {}
+                Some(_) => {
+                    unbind(&bound, bindings);
+                    return None;
+                }
+                None => {
+                    bindings[*variable] = Some(value.clone());
+                    bound.push(*variable);
+                }
+            },
+            Term::Literal(_) => {
+                unbind(&bound, bindings);
+                return None;
+            }
+        }
+    }
+
+    Some(bound)
+}
+
+fn unbind(variables: &[usize], bindings: &mut Bindings) {
+    for &variable in variables {
+        bindings[variable] = None;
+    }
+}
+
+/// Checks whether two terms are identical, as Prolog's `==` does.
+fn identical(left: &Term, right: &Term, bindings: &Bindings) -> bool {
+    match (value(left, bindings), value(right, bindings)) {
+        (Some(left), Some(right)) => left == right,
+        (None, None) => left == right,
+        _ => false,
+    }
+}
+
+/// Checks whether two terms satisfy a comparison.
+fn satisfies(comparison: Comparison, left: &Term, right: &Term, bindings: &Bindings) -> bool {
+    if comparison == Comparison::Eq {
+        return identical(left, right, bindings);
+    }
+
+    match (value(left, bindings), value(right, bindings)) {
+        (Some(left), Some(right)) => compare_numbers(comparison, &left, &right),
+        _ => false,
+    }
+}
+
+/// The facts that can be derived from a program.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct Model {
+    relations: HashMap<Relationship, Relation>,
+}
+
+impl Model {
+    /// Evaluates a program bottom-up, deriving every fact that follows from it.
+    /// Each relationship is evaluated in its stratum, after every earlier stratum.
+    pub(super) fn new(
+        rules: &HashMap<Relationship, Vec<Rule>>,
+        strata: &HashMap<Relationship, usize>,
+    ) -> Self {
+        let _enter = debug_span!("fixpoint").entered();
+
+        // Evaluate the relationships in a fixed order, so that solutions are always found in the same order
+        let mut relationships: Vec<_> = rules.keys().collect();
+        relationships.sort();
+
+        let conjunctions: Vec<_> = relationships
+            .iter()
+            .flat_map(|&relationship| {
+                rules[relationship]
+                    .iter()
+                    .flat_map(move |rule| Conjunction::from_rule(relationship, rule))
+            })
+            .collect();
+
+        let stratum_of = |relationship| strata.get(relationship).copied().unwrap_or(0);
+        let domain = domain(rules);
+
+        let mut model = Model::default();
+        for stratum in 0..=strata.values().copied().max().unwrap_or(0) {
+            let _enter = debug_span!("stratum", stratum).entered();
+            let stratum: Vec<_> = conjunctions
+                .iter()
+                .filter(|conjunction| stratum_of(conjunction.relationship) == stratum)
+                .collect();
+            model.fixpoint(&stratum, &domain);
+        }
+
+        model
+    }
+
+    /// Adds the facts derived from a stratum of rules until no new facts can be derived, using semi-naive iteration.
+    fn fixpoint(&mut self, conjunctions: &[&Conjunction], domain: &[Arc<str>]) {
+        // The first round considers every fact
+        let mut derived = Vec::new();
+        for conjunction in conjunctions {
+            self.derive(conjunction, None, domain, &mut derived);
+        }
+        let mut delta = self.insert(derived);
+
+        // Later rounds only consider derivations that use at least one fact from the previous round
+        let mut round = 1;
+        while !delta.is_empty() {
+            trace!(
+                round,
+                facts = delta
+                    .values()
+                    .map(|relation| relation.tuples.len())
+                    .sum::<usize>(),
+                "new facts"
+            );
+
+            let mut derived = Vec::new();
+            for conjunction in conjunctions {
+                for (position, literal) in conjunction.body.iter().enumerate() {
+                    if let Literal::Positive(relationship, _) = literal {
+                        if let Some(relation) = delta.get(*relationship) {
+                            self.derive(
+                                conjunction,
+                                Some((position, relation)),
+                                domain,
+                                &mut derived,
+                            );
+                        }
+                    }
+                }
+            }
+
+            delta = self.insert(derived);
+            round += 1;
+        }
+
+        debug!(rounds = round, "reached fixpoint");
+    }
+
+    /// Adds derived facts to the model.
+    /// Returns the facts that were not already known.
+    fn insert(&mut self, derived: Vec<(Relationship, Tuple)>) -> HashMap<Relationship, Relation> {
+        let mut new = HashMap::<Relationship, Relation>::new();
+        for (relationship, tuple) in derived {
+            let relation = self.relations.entry(relationship.clone()).or_default();
+            if relation.insert(tuple.clone()) {
+                new.entry(relationship).or_default().insert(tuple);
+            }
+        }
+
+        new
+    }
+
+    /// Derives the facts that follow from a rule, adding the head of the rule for each way its literals can be satisfied.
+    /// If 'delta' is given, the literal at its position only matches the facts in it.
+    /// The search backtracks through a stack of the positive literals it has matched, so a rule with any number of literals can't overflow the stack.
+    fn derive(
+        &self,
+        conjunction: &Conjunction,
+        delta: Option<(usize, &Relation)>,
+        domain: &[Arc<str>],
+        derived: &mut Vec<(Relationship, Tuple)>,
+    ) {
+        let mut bindings = vec![None; conjunction.variables];
+        // Each frame holds the position of a positive literal, the index of the fact it matched and the variables that the match bound
+        let mut frames: Vec<(usize, usize, Vec<usize>)> = Vec::new();
+        let mut position = 0;
+        let mut start = 0;
+
+        loop {
+            let satisfied = match conjunction.body.get(position) {
+                None => {
+                    ground(conjunction, domain, &mut bindings, derived);
+                    false
+                }
+                Some(&Literal::Positive(relationship, arguments)) => {
+                    let relation = match delta {
+                        Some((delta_position, relation)) if delta_position == position => {
+                            Some(relation)
+                        }
+                        _ => self.relations.get(relationship),
+                    };
+                    let tuples = relation.map_or(&[][..], |relation| &relation.tuples);
+
+                    let matched = tuples[start..].iter().enumerate().find_map(|(i, tuple)| {
+                        match_tuple(arguments, tuple, &mut bindings).map(|bound| (start + i, bound))
+                    });
+                    match matched {
+                        Some((index, bound)) => {
+                            frames.push((position, index, bound));
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                Some(&Literal::Negative(relationship, arguments)) => {
+                    !self.contains(relationship, arguments, &mut bindings)
+                }
+                Some(&Literal::Compare(comparison, left, right)) => {
+                    satisfies(comparison, left, right, &bindings)
+                }
+                Some(&Literal::NotCompare(comparison, left, right)) => {
+                    !satisfies(comparison, left, right, &bindings)
+                }
+            };
+
+            if satisfied {
+                position += 1;
+                start = 0;
+                continue;
+            }
+
+            // Only positive literals can be satisfied in another way, by the next fact that matches them
+            let Some((last, index, bound)) = frames.pop() else {
+                return;
+            };
+            unbind(&bound, &mut bindings);
+            position = last;
+            start = index + 1;
+        }
+    }
+
+    /// Checks whether any known fact matches the arguments, treating unbound variables as wildcards.
+    fn contains(
+        &self,
+        relationship: &Relationship,
+        arguments: &[Term],
+        bindings: &mut Bindings,
+    ) -> bool {
+        self.relations
+            .get(relationship)
+            .into_iter()
+            .flat_map(|relation| &relation.tuples)
+            .any(|tuple| match match_tuple(arguments, tuple, bindings) {
+                Some(bound) => {
+                    unbind(&bound, bindings);
+                    true
+                }
+                None => false,
+            })
+    }
+
+    /// Answers a query from the derived facts.
+    pub(super) fn solutions(&self, query: &Query) -> Vec<Solution> {
+        let mut bindings = vec![None; query.variables.len()];
+        let (relationship, arguments) = match &query.goal {
+            Goal::Call {
+                relationship,
+                arguments,
+            } => (relationship, arguments),
+            // A comparison binds none of its variables, so it has one solution if it holds
+            Goal::Compare(comparison, left, right) => {
+                return if satisfies(*comparison, left, right, &bindings) {
+                    vec![query
+                        .variables
+                        .iter()
+                        .map(|name| (name.clone(), "_".to_string()))
+                        .collect()]
+                } else {
+                    Vec::new()
+                };
+            }
+            _ => return Vec::new(),
+        };
+
+        let mut solutions = Vec::new();
+        for tuple in self
+            .relations
+            .get(relationship)
+            .iter()
+            .flat_map(|relation| &relation.tuples)
+        {
+            if let Some(bound) = match_tuple(arguments, tuple, &mut bindings) {
+                solutions.push(
+                    query
+                        .variables
+                        .iter()
+                        .zip(&bindings)
+                        .map(|(name, value)| {
+                            (name.clone(), value.as_deref().unwrap_or("_").to_string())
+                        })
+                        .collect(),
+                );
+                unbind(&bound, &mut bindings);
+            }
+        }
+
+        debug!(solutions = solutions.len(), "answered query");
+        solutions
+    }
+}
+
+/// Adds the head of a rule, binding any of its variables that the body left unbound to every literal in the program in turn.
+fn ground(
+    conjunction: &Conjunction,
+    domain: &[Arc<str>],
+    bindings: &mut Bindings,
+    derived: &mut Vec<(Relationship, Tuple)>,
+) {
+    let mut unbound = Vec::new();
+    for term in conjunction.head {
+        if let Term::Variable(variable) = *term {
+            if bindings[variable].is_none() && !unbound.contains(&variable) {
+                unbound.push(variable);
+            }
+        }
+    }
+    if !unbound.is_empty() && domain.is_empty() {
+        return;
+    }
+
+    // Each unbound variable counts through the domain like a digit, with the last variable counting fastest
+    let mut counter = vec![0; unbound.len()];
+    loop {
+        for (&variable, &i) in unbound.iter().zip(&counter) {
+            bindings[variable] = Some(domain[i].clone());
+        }
+        let tuple = conjunction
+            .head
+            .iter()
+            .map(|term| value(term, bindings).expect("every variable in the head is bound"))
+            .collect();
+        derived.push((conjunction.relationship.clone(), tuple));
+
+        let Some(digit) = counter.iter().rposition(|&i| i + 1 < domain.len()) else {
+            break;
+        };
+        counter[digit] += 1;
+        counter[digit + 1..].fill(0);
+    }
+
+    unbind(&unbound, bindings);
+}
+
+/// Collects every literal used in a program, in the order of the relationships.
+fn domain(rules: &HashMap<Relationship, Vec<Rule>>) -> Vec<Arc<str>> {
+    fn add(term: &Term, domain: &mut Vec<Arc<str>>, seen: &mut HashSet<Arc<str>>) {
+        if let Term::Literal(literal) = term {
+            if seen.insert(literal.clone()) {
+                domain.push(literal.clone());
+            }
+        }
+    }
+
+    fn collect(goal: &Goal, domain: &mut Vec<Arc<str>>, seen: &mut HashSet<Arc<str>>) {
+        match goal {
+            Goal::Call { arguments, .. } => {
+                for argument in arguments {
+                    add(argument, domain, seen);
+                }
+            }
+            Goal::Compare(_, left, right) => {
+                add(left, domain, seen);
+                add(right, domain, seen);
+            }
+            Goal::Not(goal) => collect(goal, domain, seen),
+            Goal::And(goals) | Goal::Or(goals) => {
+                for goal in goals {
+                    collect(goal, domain, seen);
+                }
+            }
+        }
+    }
+
+    let mut relationships: Vec<_> = rules.keys().collect();
+    relationships.sort();
+
+    let mut domain = Vec::new();
+    let mut seen = HashSet::new();
+    for rule in relationships
+        .into_iter()
+        .flat_map(|relationship| &rules[relationship])
+    {
+        for term in &rule.head {
+            add(term, &mut domain, &mut seen);
+        }
+        if let Some(body) = &rule.body {
+            collect(body, &mut domain, &mut seen);
+        }
+    }
+
+    domain
+}
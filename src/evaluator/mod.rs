@@ -0,0 +1,949 @@
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt, mem,
+    rc::Rc,
+    sync::Arc,
+    vec,
+};
+
+use tracing::{debug, debug_span, trace};
+
+use crate::{
+    parser::{
+        self,
+        ast::{self, Comparison, IdenType},
+        Location, ParseError,
+    },
+    stratification::{self, StratificationError},
+    transpiler, Solution,
+};
+
+mod datalog;
+
+/// The maximum depth of a proof before evaluation is abandoned.
+const MAX_DEPTH: usize = 100_000;
+
+/// The maximum number of negations that can be evaluated inside each other.
+const MAX_NEGATION_DEPTH: usize = 1_000;
+
+/// The maximum number of conjunctions that bottom-up evaluation can split the condition of a rule into.
+const MAX_DISJUNCTS: usize = 4_096;
+
+/// An error raised while evaluating Co-log.
+#[derive(Debug)]
+pub enum EvaluationError {
+    /// The Co-log source could not be parsed.
+    Parse(ParseError),
+
+    /// A proof grew deeper than the evaluator allows, which usually means that a rule depends on itself.
+    DepthExceeded,
+
+    /// A relationship depends on its own negation, so it has no well-defined meaning.
+    Unstratifiable(StratificationError),
+
+    /// A variable in a rule is only used in negated conditions or comparisons, so bottom-up evaluation can't find its values.
+    Unsafe {
+        variable: String,
+        location: Location,
+        statement: String,
+    },
+
+    /// A rule has too many combinations of alternatives in its condition for bottom-up evaluation, which considers each of them separately.
+    TooManyAlternatives {
+        location: Location,
+        statement: String,
+    },
+}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvaluationError::Parse(err) => write!(f, "{err}"),
+            EvaluationError::DepthExceeded => {
+                write!(f, "the query recursed too deeply to be answered")
+            }
+            EvaluationError::Unstratifiable(err) => write!(f, "{err}"),
+            EvaluationError::Unsafe {
+                variable,
+                location,
+                statement,
+            } => write!(
+                f,
+                "{location}: '{variable}' must appear in a condition that isn't negated or a comparison: {statement}"
+            ),
+            EvaluationError::TooManyAlternatives {
+                location,
+                statement,
+            } => write!(
+                f,
+                "{location}: the condition has too many alternatives to evaluate bottom-up: {statement}"
+            ),
+        }
+    }
+}
+
+impl Error for EvaluationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EvaluationError::Parse(err) => Some(err),
+            EvaluationError::Unstratifiable(err) => Some(err),
+            EvaluationError::DepthExceeded
+            | EvaluationError::Unsafe { .. }
+            | EvaluationError::TooManyAlternatives { .. } => None,
+        }
+    }
+}
+
+impl From<ParseError> for EvaluationError {
+    fn from(value: ParseError) -> Self {
+        EvaluationError::Parse(value)
+    }
+}
+
+impl From<StratificationError> for EvaluationError {
+    fn from(value: StratificationError) -> Self {
+        EvaluationError::Unstratifiable(value)
+    }
+}
+
+/// A literal or a variable.
+/// In a rule, variables are numbered from zero; during evaluation, they index into the bindings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Term {
+    Literal(Arc<str>),
+    Variable(usize),
+}
+
+impl Term {
+    /// Moves the variables in a term from a rule into the bindings, starting at 'offset'.
+    fn offset(&self, offset: usize) -> Term {
+        match self {
+            Term::Literal(_) => self.clone(),
+            Term::Variable(variable) => Term::Variable(variable + offset),
+        }
+    }
+}
+
+use stratification::Relationship;
+
+/// Checks whether two literals are numbers that satisfy an arithmetic comparison.
+/// As in the generated Prolog, the comparison fails if either literal isn't a number.
+fn compare_numbers(comparison: Comparison, left: &str, right: &str) -> bool {
+    let number = |literal: &str| {
+        literal
+            .starts_with(|c: char| c.is_ascii_digit())
+            .then(|| literal.parse::<f64>().ok())
+            .flatten()
+    };
+    let (Some(left), Some(right)) = (number(left), number(right)) else {
+        return false;
+    };
+
+    match comparison {
+        Comparison::Eq => left == right,
+        Comparison::Gt => left > right,
+        Comparison::Lt => left < right,
+        Comparison::Ge => left >= right,
+        Comparison::Le => left <= right,
+    }
+}
+
+/// A goal in the body of a rule, or a query.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Goal {
+    Call {
+        relationship: Relationship,
+        arguments: Vec<Term>,
+    },
+    Compare(Comparison, Term, Term),
+    Not(Box<Goal>),
+    /// A conjunction of at least two goals.
+    And(Vec<Goal>),
+    /// A disjunction of at least two goals.
+    Or(Vec<Goal>),
+}
+
+impl Goal {
+    /// Gets the variables that every proof of the goal binds, as every way of proving it calls a relationship with them.
+    fn bound(&self) -> HashSet<usize> {
+        match self {
+            Goal::Call { arguments, .. } => arguments
+                .iter()
+                .filter_map(|argument| match argument {
+                    Term::Variable(variable) => Some(*variable),
+                    Term::Literal(_) => None,
+                })
+                .collect(),
+            Goal::Compare(..) | Goal::Not(_) => HashSet::new(),
+            Goal::And(goals) => goals.iter().flat_map(Goal::bound).collect(),
+            Goal::Or(goals) => goals
+                .iter()
+                .map(Goal::bound)
+                .reduce(|left, right| &left & &right)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Finds a variable in a negation or a comparison that isn't bound by a call alongside it, or by 'bound'.
+    /// Top-down evaluation can bind such a variable from the query, but bottom-up evaluation can't, so the strategies would disagree.
+    fn unsafe_variable(&self, bound: &HashSet<usize>) -> Option<usize> {
+        let unbound = |term: &Term| match term {
+            Term::Variable(variable) if !bound.contains(variable) => Some(*variable),
+            _ => None,
+        };
+
+        match self {
+            Goal::Call { .. } => None,
+            Goal::Compare(_, left, right) => unbound(left).or_else(|| unbound(right)),
+            Goal::Not(goal) => match goal.as_ref() {
+                Goal::Call { arguments, .. } => arguments.iter().find_map(unbound),
+                goal => goal.unsafe_variable(bound),
+            },
+            Goal::And(goals) => {
+                let mut bound = bound.clone();
+                bound.extend(goals.iter().flat_map(Goal::bound));
+                goals.iter().find_map(|goal| goal.unsafe_variable(&bound))
+            }
+            Goal::Or(goals) => goals.iter().find_map(|goal| goal.unsafe_variable(bound)),
+        }
+    }
+
+    /// Counts the conjunctions that bottom-up evaluation splits the goal into, one for each way of choosing an alternative of every disjunction.
+    /// The count saturates instead of overflowing, as it grows exponentially with the disjunctions in a conjunction.
+    fn disjuncts(&self) -> usize {
+        match self {
+            Goal::Call { .. } | Goal::Compare(..) | Goal::Not(_) => 1,
+            Goal::And(goals) => goals
+                .iter()
+                .fold(1, |count, goal| count.saturating_mul(goal.disjuncts())),
+            Goal::Or(goals) => goals
+                .iter()
+                .fold(0, |count, goal| count.saturating_add(goal.disjuncts())),
+        }
+    }
+}
+
+/// A fact, or a rule with a body.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Rule {
+    head: Vec<Term>,
+    body: Option<Goal>,
+    variables: usize,
+}
+
+/// Numbers the variables within a single statement.
+#[derive(Debug, Default)]
+struct Scope {
+    variables: Vec<String>,
+}
+
+impl Scope {
+    /// Converts an identifier into a term, numbering it if it is a new variable.
+    fn term(&mut self, identifier: &ast::Identifier) -> Term {
+        match identifier.kind() {
+            IdenType::Literal | IdenType::Verb | IdenType::Plural => {
+                Term::Literal(identifier.name().into())
+            }
+            IdenType::Number => Term::Literal(identifier.lexeme().into()),
+            IdenType::Variable => {
+                let position = self
+                    .variables
+                    .iter()
+                    .position(|variable| variable == identifier.lexeme());
+                match position {
+                    Some(position) => Term::Variable(position),
+                    None => {
+                        self.variables.push(identifier.lexeme().to_string());
+                        Term::Variable(self.variables.len() - 1)
+                    }
+                }
+            }
+            // Each pronoun is a separate variable, even if the same pronoun is used twice, so a repeated pronoun is numbered like 'who_2'
+            IdenType::Pronoun => {
+                let name = transpiler::unique(identifier.lexeme().to_lowercase(), |name| {
+                    self.variables.iter().any(|variable| variable == name)
+                });
+                self.variables.push(name);
+                Term::Variable(self.variables.len() - 1)
+            }
+        }
+    }
+
+    /// Converts the identifiers of a statement or clause into the name of its relationship and its arguments.
+    fn arguments(
+        &mut self,
+        left: &ast::Identifier,
+        relationship: &ast::Identifier,
+        right: &[ast::Identifier],
+    ) -> (Relationship, Vec<Term>) {
+        let mut arguments = vec![self.term(left)];
+        arguments.extend(right.iter().map(|right| self.term(right)));
+
+        ((relationship.name(), arguments.len()), arguments)
+    }
+
+    /// Converts the identifiers of a simple clause or query into a goal, which is a comparison if its relationship is one.
+    fn simple(
+        &mut self,
+        left: &ast::Identifier,
+        relationship: &ast::Identifier,
+        right: &[ast::Identifier],
+    ) -> Goal {
+        let comparison = Comparison::from_relationship(relationship, right);
+        let (relationship, mut arguments) = self.arguments(left, relationship, right);
+        if let Some(comparison) = comparison {
+            let right = arguments.pop().unwrap();
+            let left = arguments.pop().unwrap();
+            Goal::Compare(comparison, left, right)
+        } else {
+            Goal::Call {
+                relationship,
+                arguments,
+            }
+        }
+    }
+
+    /// Converts the condition of a rule into a goal.
+    fn goal(&mut self, clause: &ast::Clause) -> Goal {
+        match clause {
+            ast::Clause::Simple {
+                negated,
+                left,
+                relationship,
+                right,
+            } => {
+                let goal = self.simple(left, relationship, right);
+                if *negated {
+                    Goal::Not(Box::new(goal))
+                } else {
+                    goal
+                }
+            }
+            ast::Clause::Operator { op_type, clauses } => {
+                let goals = clauses.iter().map(|clause| self.goal(clause)).collect();
+                match op_type {
+                    ast::OperatorType::And => Goal::And(goals),
+                    ast::OperatorType::Or => Goal::Or(goals),
+                }
+            }
+        }
+    }
+}
+
+/// A query that can be answered by an evaluator.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Query {
+    kind: ast::QueryType,
+    goal: Goal,
+    variables: Vec<String>,
+}
+
+impl Query {
+    /// Converts a query statement into a query.
+    fn new(tree: &ast::Stmt) -> Self {
+        let mut scope = Scope::default();
+        let goal = scope.simple(tree.left(), tree.relationship(), tree.right());
+
+        let ast::StmtType::Query(kind) = tree.kind() else {
+            unreachable!("only queries are converted into queries")
+        };
+        Query {
+            kind,
+            goal,
+            variables: scope.variables,
+        }
+    }
+}
+
+/// A point in the bindings that they can be restored to when backtracking.
+#[derive(Debug, Clone, Copy)]
+struct Mark {
+    trail: usize,
+    values: usize,
+}
+
+/// The values bound to the variables in a proof, with a trail of the bindings that were made so that they can be undone.
+#[derive(Debug, Default)]
+struct Bindings {
+    values: Vec<Option<Term>>,
+    trail: Vec<usize>,
+}
+
+impl Bindings {
+    /// Allocates 'count' unbound variables, returning the index of the first.
+    fn allocate(&mut self, count: usize) -> usize {
+        let offset = self.values.len();
+        self.values.resize(offset + count, None);
+        offset
+    }
+
+    /// Follows a term's bindings until it reaches a literal or an unbound variable.
+    fn resolve(&self, term: &Term) -> Term {
+        let mut term = term.clone();
+        while let Term::Variable(variable) = term {
+            match &self.values[variable] {
+                Some(value) => term = value.clone(),
+                None => break,
+            }
+        }
+
+        term
+    }
+
+    /// Unifies two terms, binding variables as necessary.
+    /// Returns false if the terms cannot be unified.
+    fn unify(&mut self, left: &Term, right: &Term) -> bool {
+        match (self.resolve(left), self.resolve(right)) {
+            (Term::Literal(left), Term::Literal(right)) => left == right,
+            (Term::Variable(left), Term::Variable(right)) if left == right => true,
+            (Term::Variable(variable), value) | (value, Term::Variable(variable)) => {
+                self.values[variable] = Some(value);
+                self.trail.push(variable);
+                true
+            }
+        }
+    }
+
+    fn mark(&self) -> Mark {
+        Mark {
+            trail: self.trail.len(),
+            values: self.values.len(),
+        }
+    }
+
+    /// Undoes every binding and allocation made since 'mark'.
+    fn undo(&mut self, mark: Mark) {
+        for variable in self.trail.drain(mark.trail..) {
+            self.values[variable] = None;
+        }
+        self.values.truncate(mark.values);
+    }
+}
+
+/// A goal that remains to be proven, followed by the goals after it.
+#[derive(Debug)]
+struct Continuation<'a> {
+    goal: &'a Goal,
+    offset: usize,
+    depth: usize,
+    next: Option<Rc<Continuation<'a>>>,
+}
+
+impl Drop for Continuation<'_> {
+    /// Drops the goals that follow iteratively, as a deep proof can leave a chain too long to drop recursively.
+    fn drop(&mut self) {
+        let mut next = self.next.take();
+        while let Some(continuation) = next {
+            next = match Rc::try_unwrap(continuation) {
+                Ok(mut continuation) => continuation.next.take(),
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+/// A point that the search can backtrack to.
+#[derive(Debug)]
+enum Choice<'a> {
+    /// The remaining rules that could prove a call.
+    Rules {
+        rules: &'a [Rule],
+        arguments: &'a [Term],
+        offset: usize,
+        depth: usize,
+        next: Option<Rc<Continuation<'a>>>,
+        mark: Mark,
+    },
+
+    /// One of the goals after the first in a disjunction.
+    Goal {
+        goal: &'a Goal,
+        offset: usize,
+        depth: usize,
+        next: Option<Rc<Continuation<'a>>>,
+        mark: Mark,
+    },
+}
+
+/// A depth-first search for proofs of a goal, using SLD resolution.
+#[derive(Debug)]
+struct Machine<'a> {
+    rules: &'a HashMap<Relationship, Vec<Rule>>,
+    bindings: Bindings,
+    goals: Option<Rc<Continuation<'a>>>,
+    choices: Vec<Choice<'a>>,
+    negation_depth: usize,
+}
+
+impl<'a> Machine<'a> {
+    /// Tries to prove a call with each of 'rules' in turn, leaving a choice point for the rules after the first that applies.
+    /// Returns false if no rule applies.
+    fn try_rules(
+        &mut self,
+        rules: &'a [Rule],
+        arguments: &'a [Term],
+        offset: usize,
+        depth: usize,
+        next: Option<Rc<Continuation<'a>>>,
+    ) -> bool {
+        for (i, rule) in rules.iter().enumerate() {
+            let mark = self.bindings.mark();
+            let rule_offset = self.bindings.allocate(rule.variables);
+
+            let unified = arguments.iter().zip(&rule.head).all(|(argument, head)| {
+                self.bindings
+                    .unify(&argument.offset(offset), &head.offset(rule_offset))
+            });
+            if !unified {
+                self.bindings.undo(mark);
+                continue;
+            }
+
+            if i + 1 < rules.len() {
+                self.choices.push(Choice::Rules {
+                    rules: &rules[i + 1..],
+                    arguments,
+                    offset,
+                    depth,
+                    next: next.clone(),
+                    mark,
+                });
+            }
+
+            self.goals = match &rule.body {
+                Some(body) => Some(Rc::new(Continuation {
+                    goal: body,
+                    offset: rule_offset,
+                    depth: depth + 1,
+                    next,
+                })),
+                None => next,
+            };
+            return true;
+        }
+
+        false
+    }
+
+    /// Checks whether a goal can be proven, without keeping any of the bindings made while proving it.
+    fn prove(
+        &mut self,
+        goal: &'a Goal,
+        offset: usize,
+        depth: usize,
+    ) -> Result<bool, EvaluationError> {
+        if self.negation_depth >= MAX_NEGATION_DEPTH {
+            return Err(EvaluationError::DepthExceeded);
+        }
+
+        let mark = self.bindings.mark();
+        let mut machine = Machine {
+            rules: self.rules,
+            bindings: mem::take(&mut self.bindings),
+            goals: Some(Rc::new(Continuation {
+                goal,
+                offset,
+                depth,
+                next: None,
+            })),
+            choices: Vec::new(),
+            negation_depth: self.negation_depth + 1,
+        };
+        let result = machine.run();
+
+        self.bindings = machine.bindings;
+        self.bindings.undo(mark);
+
+        result
+    }
+
+    /// Attempts to prove the first goal in 'continuation', setting the goals that remain afterwards.
+    /// Returns false if the goal could not be proven.
+    fn step(&mut self, continuation: &Continuation<'a>) -> Result<bool, EvaluationError> {
+        let Continuation {
+            goal,
+            offset,
+            depth,
+            ref next,
+        } = *continuation;
+
+        Ok(match goal {
+            Goal::Call {
+                relationship,
+                arguments,
+            } => {
+                trace!(relationship = relationship.0, depth, "calling");
+                match self.rules.get(relationship) {
+                    Some(rules) => self.try_rules(rules, arguments, offset, depth, next.clone()),
+                    None => false,
+                }
+            }
+            Goal::Compare(comparison, left, right) => {
+                self.goals = next.clone();
+                match (
+                    comparison,
+                    self.bindings.resolve(&left.offset(offset)),
+                    self.bindings.resolve(&right.offset(offset)),
+                ) {
+                    (Comparison::Eq, left, right) => left == right,
+                    (_, Term::Literal(left), Term::Literal(right)) => {
+                        compare_numbers(*comparison, &left, &right)
+                    }
+                    _ => false,
+                }
+            }
+            Goal::Not(goal) => {
+                self.goals = next.clone();
+                !self.prove(goal, offset, depth + 1)?
+            }
+            Goal::And(goals) => {
+                self.goals = goals.iter().rev().fold(next.clone(), |next, goal| {
+                    Some(Rc::new(Continuation {
+                        goal,
+                        offset,
+                        depth: depth + 1,
+                        next,
+                    }))
+                });
+                true
+            }
+            Goal::Or(goals) => {
+                // The choice for the second goal is pushed last, so that it is tried first
+                let mark = self.bindings.mark();
+                for goal in goals[1..].iter().rev() {
+                    self.choices.push(Choice::Goal {
+                        goal,
+                        offset,
+                        depth: depth + 1,
+                        next: next.clone(),
+                        mark,
+                    });
+                }
+                self.goals = Some(Rc::new(Continuation {
+                    goal: &goals[0],
+                    offset,
+                    depth: depth + 1,
+                    next: next.clone(),
+                }));
+                true
+            }
+        })
+    }
+
+    /// Returns to the most recent choice point.
+    /// Returns false if there are no choice points left.
+    fn backtrack(&mut self) -> bool {
+        while let Some(choice) = self.choices.pop() {
+            match choice {
+                Choice::Rules {
+                    rules,
+                    arguments,
+                    offset,
+                    depth,
+                    next,
+                    mark,
+                } => {
+                    self.bindings.undo(mark);
+                    if self.try_rules(rules, arguments, offset, depth, next) {
+                        return true;
+                    }
+                }
+                Choice::Goal {
+                    goal,
+                    offset,
+                    depth,
+                    next,
+                    mark,
+                } => {
+                    self.bindings.undo(mark);
+                    self.goals = Some(Rc::new(Continuation {
+                        goal,
+                        offset,
+                        depth,
+                        next,
+                    }));
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Searches for the next proof.
+    /// Returns false if there are no more proofs.
+    fn run(&mut self) -> Result<bool, EvaluationError> {
+        while let Some(continuation) = self.goals.take() {
+            if continuation.depth > MAX_DEPTH {
+                return Err(EvaluationError::DepthExceeded);
+            }
+
+            if !self.step(&continuation)? && !self.backtrack() {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// A search for the proofs of a query, which searches for each proof only when it is requested.
+#[derive(Debug)]
+struct Proofs<'a> {
+    query: &'a Query,
+    machine: Machine<'a>,
+    started: bool,
+    finished: bool,
+}
+
+impl<'a> Proofs<'a> {
+    /// Reads the values bound to the query's variables.
+    fn read_solution(&self) -> Solution {
+        self.query
+            .variables
+            .iter()
+            .enumerate()
+            .map(|(variable, name)| {
+                let value = match self.machine.bindings.resolve(&Term::Variable(variable)) {
+                    Term::Literal(literal) => literal.to_string(),
+                    Term::Variable(_) => String::from("_"),
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+}
+
+impl<'a> Iterator for Proofs<'a> {
+    type Item = Result<Solution, EvaluationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        if self.started && !self.machine.backtrack() {
+            self.finished = true;
+            return None;
+        }
+        self.started = true;
+
+        match self.machine.run() {
+            Ok(true) => {
+                let solution = self.read_solution();
+                debug!(?solution, "found solution");
+                Some(Ok(solution))
+            }
+            Ok(false) => {
+                debug!("no more solutions");
+                self.finished = true;
+                None
+            }
+            Err(err) => {
+                self.finished = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// The source of the solutions to a query.
+#[derive(Debug)]
+enum Search<'a> {
+    TopDown(Proofs<'a>),
+    BottomUp(vec::IntoIter<Solution>),
+}
+
+/// An iterator over the solutions to a query.
+/// Under top-down evaluation, each solution is only searched for when it is requested.
+#[derive(Debug)]
+pub struct Solutions<'a> {
+    search: Search<'a>,
+}
+
+impl<'a> Iterator for Solutions<'a> {
+    type Item = Result<Solution, EvaluationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.search {
+            Search::TopDown(proofs) => proofs.next(),
+            Search::BottomUp(solutions) => solutions.next().map(Ok),
+        }
+    }
+}
+
+/// The order in which an evaluator works through a program.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Strategy {
+    /// Searches depth-first from the query, as Prolog does.
+    /// Solutions are found lazily, but left-recursive rules never terminate.
+    #[default]
+    TopDown,
+
+    /// Derives every fact that follows from the program before answering queries, treating it as Datalog.
+    /// Always terminates, but variables that the program leaves unbound are replaced with every literal in the program.
+    BottomUp,
+}
+
+/// A knowledge base built from Co-log source, which answers queries without an external Prolog installation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Evaluator {
+    rules: HashMap<Relationship, Vec<Rule>>,
+    statements: Vec<(ast::Stmt, Location)>,
+    strata: HashMap<Relationship, usize>,
+    strategy: Strategy,
+    model: OnceCell<datalog::Model>,
+}
+
+impl Evaluator {
+    /// Creates an evaluator with an empty knowledge base.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an evaluator with an empty knowledge base, which evaluates queries with the given strategy.
+    pub fn with_strategy(strategy: Strategy) -> Self {
+        Evaluator {
+            strategy,
+            ..Self::default()
+        }
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// Adds the facts and rules in the given Co-log source to the knowledge base.
+    /// Returns any queries found in the source.
+    /// Returns an error without changing the knowledge base if a relationship would depend on its own negation,
+    /// or, under bottom-up evaluation, if a rule uses a variable in a negated condition or a comparison without binding it in another condition,
+    /// or has more combinations of alternatives in its condition than can be evaluated.
+    /// Top-down evaluation accepts such rules, as Prolog does, since the query can bind the variable.
+    pub fn load(&mut self, source: &str) -> Result<Vec<Query>, EvaluationError> {
+        let trees = parser::parse_source(source)?;
+
+        let mut queries = Vec::new();
+        let mut rules = Vec::new();
+        for tree in &trees {
+            let _enter = debug_span!("statement", kind = ?tree.kind()).entered();
+            if matches!(tree.kind(), ast::StmtType::Query(_)) {
+                queries.push(Query::new(tree));
+                continue;
+            }
+
+            let mut scope = Scope::default();
+            let (relationship, head) =
+                scope.arguments(tree.left(), tree.relationship(), tree.right());
+            let body = tree.condition().as_ref().map(|clause| scope.goal(clause));
+
+            let unsafe_variable = match self.strategy {
+                Strategy::TopDown => None,
+                Strategy::BottomUp => body
+                    .as_ref()
+                    .and_then(|body| body.unsafe_variable(&HashSet::new())),
+            };
+            if let Some(variable) = unsafe_variable {
+                debug!(variable, "unsafe rule");
+                return Err(EvaluationError::Unsafe {
+                    variable: scope.variables.swap_remove(variable),
+                    location: Location::of(source, tree.span().start),
+                    statement: tree.to_string(),
+                });
+            }
+            if self.strategy == Strategy::BottomUp
+                && body
+                    .as_ref()
+                    .is_some_and(|body| body.disjuncts() > MAX_DISJUNCTS)
+            {
+                debug!("rule with too many alternatives");
+                return Err(EvaluationError::TooManyAlternatives {
+                    location: Location::of(source, tree.span().start),
+                    statement: tree.to_string(),
+                });
+            }
+
+            rules.push((
+                relationship,
+                Rule {
+                    head,
+                    body,
+                    variables: scope.variables.len(),
+                },
+            ));
+        }
+
+        let statements = stratification::rules(source, &trees);
+        self.strata = stratification::stratify(self.statements.iter().chain(&statements))?;
+        self.statements.extend(statements);
+
+        // Any facts derived from the old knowledge base are out of date
+        self.model.take();
+
+        for (relationship, rule) in rules {
+            debug!(relationship = relationship.0, "added rule");
+            self.rules.entry(relationship).or_default().push(rule);
+        }
+
+        Ok(queries)
+    }
+
+    /// Opens a query, returning an iterator over its solutions.
+    pub fn solutions<'a>(&'a self, query: &'a Query) -> Solutions<'a> {
+        let search = match self.strategy {
+            Strategy::TopDown => Search::TopDown(self.proofs(query)),
+            Strategy::BottomUp => Search::BottomUp(self.model().solutions(query).into_iter()),
+        };
+
+        Solutions { search }
+    }
+
+    /// Gets the facts derived from the knowledge base, deriving them if they are out of date.
+    fn model(&self) -> &datalog::Model {
+        self.model
+            .get_or_init(|| datalog::Model::new(&self.rules, &self.strata))
+    }
+
+    /// Starts a top-down search for the proofs of a query.
+    fn proofs<'a>(&'a self, query: &'a Query) -> Proofs<'a> {
+        let mut bindings = Bindings::default();
+        bindings.allocate(query.variables.len());
+
+        Proofs {
+            query,
+            machine: Machine {
+                rules: &self.rules,
+                bindings,
+                goals: Some(Rc::new(Continuation {
+                    goal: &query.goal,
+                    offset: 0,
+                    depth: 0,
+                    next: None,
+                })),
+                choices: Vec::new(),
+                negation_depth: 0,
+            },
+            started: false,
+            finished: false,
+        }
+    }
+
+    /// Answers a query, returning every solution to it, or the answer it asks for such as the number of solutions.
+    pub fn query(&self, query: &Query) -> Result<Vec<Solution>, EvaluationError> {
+        let _enter = debug_span!("query").entered();
+        Ok(crate::answer(
+            query.kind,
+            self.solutions(query).collect::<Result<_, _>>()?,
+        ))
+    }
+
+    /// Parses a single Co-log query and answers it from the knowledge base.
+    pub fn ask(&self, source: &str) -> Result<Vec<Solution>, EvaluationError> {
+        let query = Query::new(&crate::parse_query(source)?);
+        self.query(&query)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,537 @@
+use super::*;
+
+fn evaluator(source: &str) -> Evaluator {
+    let mut evaluator = Evaluator::new();
+    evaluator.load(source).unwrap();
+    evaluator
+}
+
+#[test]
+fn evaluate_fact() {
+    let evaluator = evaluator("John is the brother of Jack.");
+
+    assert_eq!(
+        evaluator.ask("Is John the brother of Jack?").unwrap(),
+        vec![Solution::new()]
+    );
+    assert_eq!(
+        evaluator.ask("Is Jack the brother of John?").unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn evaluate_multi_word_names() {
+    let evaluator = evaluator(
+        "New York is the capital of New York State. \"Mary Anne\" is the mayor of \"new york\".",
+    );
+
+    assert_eq!(
+        evaluator
+            .ask("Who is the capital of New York State?")
+            .unwrap(),
+        vec![Solution::from([(
+            "who".to_string(),
+            "new york".to_string()
+        )])]
+    );
+    assert_eq!(
+        evaluator
+            .ask("Is Mary Anne the mayor of \"New York\"?")
+            .unwrap(),
+        vec![Solution::new()]
+    );
+}
+
+#[test]
+fn evaluate_pronoun() {
+    let evaluator = evaluator("John is the brother of Jack. Jim is the brother of Jack.");
+
+    assert_eq!(
+        evaluator.ask("Who is the brother of Jack?").unwrap(),
+        vec![
+            Solution::from([("who".to_string(), "john".to_string())]),
+            Solution::from([("who".to_string(), "jim".to_string())]),
+        ]
+    );
+}
+
+#[test]
+fn evaluate_rule() {
+    let evaluator = evaluator(
+        "A hamster is an animal. A hamster is warm-blooded. X is a mammal if X is an animal and X is warm-blooded.",
+    );
+
+    assert_eq!(
+        evaluator.ask("Is a hamster a mammal?").unwrap(),
+        vec![Solution::new()]
+    );
+}
+
+#[test]
+fn evaluate_negation() {
+    let evaluator = evaluator(
+        "John is the parent of Jack. John is the parent of Jane. X is the sibling of Y if Z is the parent of X and Z is the parent of Y and X is not Y.",
+    );
+
+    assert_eq!(
+        evaluator.ask("Who is the sibling of Jack?").unwrap(),
+        vec![Solution::from([("who".to_string(), "jane".to_string())])]
+    );
+    assert_eq!(
+        evaluator.ask("Is Jack the sibling of Jack?").unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn evaluate_or() {
+    let evaluator = evaluator(
+        "Ann is one. Bob is one. Cat is two. X is thing of Y if (X is one and Y is one) or (X is two and Y is two).",
+    );
+
+    assert_eq!(
+        evaluator.ask("What is thing of Cat?").unwrap(),
+        vec![Solution::from([("what".to_string(), "cat".to_string())])]
+    );
+    assert_eq!(evaluator.ask("What is thing of Ann?").unwrap().len(), 2);
+}
+
+#[test]
+fn evaluate_long_chain() {
+    let source = format!(
+        "Ann is a thing. X is good if X is a thing{}. X is bad if X is a thing{}.",
+        " and X is a thing".repeat(1_000),
+        " or X is a nothing".repeat(1_000)
+    );
+
+    for strategy in [Strategy::TopDown, Strategy::BottomUp] {
+        let mut evaluator = Evaluator::with_strategy(strategy);
+        evaluator.load(&source).unwrap();
+
+        assert_eq!(
+            evaluator.ask("Is Ann good?").unwrap(),
+            vec![Solution::new()]
+        );
+        assert_eq!(evaluator.ask("Is Ann bad?").unwrap(), vec![Solution::new()]);
+    }
+}
+
+#[test]
+fn evaluate_lazily() {
+    let evaluator = evaluator(
+        "John is the brother of Jack. Jim is the brother of Jack. X is the brother of Y if X is the brother of Y.",
+    );
+    let query = Query::new(&crate::parse_query("Who is the brother of Jack?").unwrap());
+
+    assert_eq!(
+        evaluator
+            .solutions(&query)
+            .take(2)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        vec![
+            Solution::from([("who".to_string(), "john".to_string())]),
+            Solution::from([("who".to_string(), "jim".to_string())]),
+        ]
+    );
+}
+
+#[test]
+fn evaluate_depth_exceeded() {
+    let evaluator = evaluator(
+        "John is the parent of Jack. X is the ancestor of Y if X is the ancestor of Z and Z is the parent of Y.",
+    );
+
+    assert!(matches!(
+        evaluator.ask("Who is the ancestor of Jack?"),
+        Err(EvaluationError::DepthExceeded)
+    ));
+}
+
+#[test]
+fn evaluate_parse_error() {
+    let evaluator = evaluator("John is the brother of Jack.");
+
+    assert!(matches!(
+        evaluator.ask("John is the brother of Jack."),
+        Err(EvaluationError::Parse(_))
+    ));
+}
+
+fn bottom_up(source: &str) -> Evaluator {
+    let mut evaluator = Evaluator::with_strategy(Strategy::BottomUp);
+    evaluator.load(source).unwrap();
+    evaluator
+}
+
+#[test]
+fn bottom_up_rule() {
+    let evaluator = bottom_up(
+        "A hamster is an animal. A hamster is warm-blooded. X is a mammal if X is an animal and X is warm-blooded.",
+    );
+
+    assert_eq!(
+        evaluator.ask("Is a hamster a mammal?").unwrap(),
+        vec![Solution::new()]
+    );
+}
+
+#[test]
+fn bottom_up_left_recursion() {
+    let evaluator = bottom_up(
+        "Ann is the parent of Bob. Bob is the parent of Cat. Cat is the parent of Dan. X is the ancestor of Y if X is the parent of Y. X is the ancestor of Y if X is the ancestor of Z and Z is the parent of Y.",
+    );
+
+    assert_eq!(
+        evaluator.ask("Who is the ancestor of Dan?").unwrap(),
+        vec![
+            Solution::from([("who".to_string(), "cat".to_string())]),
+            Solution::from([("who".to_string(), "bob".to_string())]),
+            Solution::from([("who".to_string(), "ann".to_string())]),
+        ]
+    );
+    assert_eq!(
+        evaluator.ask("Is Dan the ancestor of Ann?").unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn bottom_up_negation() {
+    let evaluator = bottom_up(
+        "John is the parent of Jack. John is the parent of Jane. X is the sibling of Y if Z is the parent of X and Z is the parent of Y and X is not Y. X is lonely if X is the parent of Y and X is not the sibling of Y.",
+    );
+
+    assert_eq!(
+        evaluator.ask("Who is the sibling of Jack?").unwrap(),
+        vec![Solution::from([("who".to_string(), "jane".to_string())])]
+    );
+    assert_eq!(
+        evaluator.ask("Is John lonely?").unwrap(),
+        vec![Solution::new()]
+    );
+}
+
+#[test]
+fn bottom_up_or() {
+    let evaluator = bottom_up(
+        "Ann is one. Bob is one. Cat is two. X is thing of Y if (X is one and Y is one) or (X is two and Y is two).",
+    );
+
+    assert_eq!(
+        evaluator.ask("What is thing of Cat?").unwrap(),
+        vec![Solution::from([("what".to_string(), "cat".to_string())])]
+    );
+    assert_eq!(evaluator.ask("What is thing of Ann?").unwrap().len(), 2);
+}
+
+#[test]
+fn bottom_up_unbound_variable() {
+    let evaluator = bottom_up("Ann is one. X is the twin of X.");
+
+    assert_eq!(
+        evaluator.ask("What is the twin of Ann?").unwrap(),
+        vec![Solution::from([("what".to_string(), "ann".to_string())])]
+    );
+}
+
+#[test]
+fn evaluate_unstratifiable() {
+    let mut evaluator = evaluator("Ann is one. X is even if X is one and X is not odd.");
+
+    assert!(matches!(
+        evaluator.load("X is odd if X is one and X is not even."),
+        Err(EvaluationError::Unstratifiable(_))
+    ));
+    assert_eq!(
+        evaluator.ask("Is Ann even?").unwrap(),
+        vec![Solution::new()]
+    );
+}
+
+#[test]
+fn evaluate_unsafe() {
+    let mut evaluator = bottom_up("Cat is a friend of Bob. Ann is a person.");
+
+    // A variable that is only negated or compared would be bound by the query top-down, but never bottom-up
+    let err = evaluator
+        .load("X is lonely if X is not a friend of Bob.")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 1, column 1: 'X' must appear in a condition that isn't negated or a comparison: X is lonely if X is not a friend of Bob."
+    );
+    assert!(matches!(
+        evaluator.load("Ann is one.\nX is the sum of Y and Z if X is Y."),
+        Err(EvaluationError::Unsafe { variable, location, .. })
+            if variable == "X" && location == Location { line: 2, column: 1 }
+    ));
+    // Each disjunct must bind the variable itself
+    assert!(matches!(
+        evaluator
+            .load("X is lonely if (X is a person or Y is a person) and X is not a friend of Bob."),
+        Err(EvaluationError::Unsafe { .. })
+    ));
+    assert_eq!(evaluator.ask("Is Ann one?").unwrap(), vec![]);
+
+    evaluator
+        .load("X is lonely if (X is a person or X is a cat) and X is not a friend of Bob.")
+        .unwrap();
+    assert_eq!(
+        evaluator.ask("Is Ann lonely?").unwrap(),
+        vec![Solution::new()]
+    );
+    assert_eq!(
+        evaluator.ask("Who is lonely?").unwrap(),
+        vec![Solution::from([("who".to_string(), "ann".to_string())])]
+    );
+}
+
+#[test]
+fn too_many_alternatives() {
+    let condition = ('a'..='m')
+        .map(|c| format!("(X is p{c} or X is q{c})"))
+        .collect::<Vec<_>>()
+        .join(" and ");
+    let source = format!("Ann is pa.\nX is picky if {condition}.");
+
+    // Each combination of alternatives would be a separate conjunction, which is too many bottom-up but fine top-down
+    let mut evaluator = bottom_up("");
+    assert!(matches!(
+        evaluator.load(&source),
+        Err(EvaluationError::TooManyAlternatives { location, .. })
+            if location == Location { line: 2, column: 1 }
+    ));
+    assert_eq!(evaluator.ask("Is Ann pa?").unwrap(), vec![]);
+
+    let mut evaluator = Evaluator::new();
+    evaluator.load(&source).unwrap();
+    assert_eq!(evaluator.ask("Is Ann picky?").unwrap(), vec![]);
+}
+
+#[test]
+fn top_down_unbound_variable() {
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .load("Cat is the friend of Bob. Ann is a person. Cat is a person.")
+        .unwrap();
+
+    // Top-down evaluation accepts rules that Prolog does, even if only the query can bind a variable
+    evaluator
+        .load(
+            "X is an adult if X is greater than 17. X is lonely if X is a person and X is not the friend of Y.",
+        )
+        .unwrap();
+    assert_eq!(
+        evaluator.ask("Is 34 an adult?").unwrap(),
+        vec![Solution::new()]
+    );
+    assert_eq!(evaluator.ask("Is 17 an adult?").unwrap(), vec![]);
+    assert_eq!(
+        evaluator.ask("Who is lonely?").unwrap(),
+        vec![Solution::from([("who".to_string(), "ann".to_string())])]
+    );
+}
+
+#[test]
+fn bottom_up_incremental() {
+    let mut evaluator = bottom_up("X is a mammal if X is an animal.");
+    assert_eq!(evaluator.ask("Is a hamster a mammal?").unwrap(), vec![]);
+
+    evaluator.load("A hamster is an animal.").unwrap();
+    assert_eq!(
+        evaluator.ask("Is a hamster a mammal?").unwrap(),
+        vec![Solution::new()]
+    );
+}
+
+#[test]
+fn evaluate_comparison() {
+    let source =
+        "Alice is 34 years old. Bob is 17 years old. Carol is 18 years old. Dave is an adult.
+        X is an adult if X is N years old and N is at least 18.
+        X is a minor if X is N years old and N is not greater than 17.";
+
+    for strategy in [Strategy::TopDown, Strategy::BottomUp] {
+        let mut evaluator = Evaluator::with_strategy(strategy);
+        evaluator.load(source).unwrap();
+
+        let mut adults = evaluator.ask("Who is an adult?").unwrap();
+        adults.sort_by(|left, right| left["who"].cmp(&right["who"]));
+        assert_eq!(
+            adults,
+            vec![
+                Solution::from([("who".to_string(), "alice".to_string())]),
+                Solution::from([("who".to_string(), "carol".to_string())]),
+                Solution::from([("who".to_string(), "dave".to_string())]),
+            ]
+        );
+        assert_eq!(
+            evaluator.ask("Who is a minor?").unwrap(),
+            vec![Solution::from([("who".to_string(), "bob".to_string())])]
+        );
+        assert_eq!(
+            evaluator.ask("Carol is what years old?").unwrap(),
+            vec![Solution::from([("what".to_string(), "18".to_string())])]
+        );
+    }
+}
+
+#[test]
+fn evaluate_repeated_pronoun() {
+    for strategy in [Strategy::TopDown, Strategy::BottomUp] {
+        let mut evaluator = Evaluator::with_strategy(strategy);
+        evaluator
+            .load("Alice is the parent of Bob. Bob is the parent of Carol.")
+            .unwrap();
+
+        // Each 'who' is bound separately, rather than the second overwriting the first
+        let mut solutions = evaluator.ask("Who is the parent of who?").unwrap();
+        solutions.sort_by_key(|solution| solution["who"].clone());
+        assert_eq!(
+            solutions,
+            vec![
+                Solution::from([
+                    ("who".to_string(), "alice".to_string()),
+                    ("who_2".to_string(), "bob".to_string()),
+                ]),
+                Solution::from([
+                    ("who".to_string(), "bob".to_string()),
+                    ("who_2".to_string(), "carol".to_string()),
+                ]),
+            ]
+        );
+    }
+}
+
+#[test]
+fn evaluate_described_comparison() {
+    let source = "34 is the age of Alice. 17 is the age of Bob.
+        X is an adult if the age of X is greater than 17.";
+
+    for strategy in [Strategy::TopDown, Strategy::BottomUp] {
+        let mut evaluator = Evaluator::with_strategy(strategy);
+        evaluator.load(source).unwrap();
+
+        assert_eq!(
+            evaluator.ask("Who is an adult?").unwrap(),
+            vec![Solution::from([("who".to_string(), "alice".to_string())])]
+        );
+        assert_eq!(
+            evaluator.ask("Is 34 greater than 17?").unwrap(),
+            vec![Solution::new()]
+        );
+        assert_eq!(evaluator.ask("Is 17 at least 34?").unwrap(), vec![]);
+    }
+}
+
+#[test]
+fn evaluate_n_ary() {
+    let source = "Alice is the sender of a letter to Bob. Carol is the sender of a parcel to Bob.
+        X is the recipient of Y from Z if Z is the sender of Y to X.";
+
+    for strategy in [Strategy::TopDown, Strategy::BottomUp] {
+        let mut evaluator = Evaluator::with_strategy(strategy);
+        evaluator.load(source).unwrap();
+
+        assert_eq!(
+            evaluator
+                .ask("Is Bob the recipient of a letter from Alice?")
+                .unwrap(),
+            vec![Solution::new()]
+        );
+        assert_eq!(
+            evaluator
+                .ask("Is Bob the recipient of a parcel from Alice?")
+                .unwrap(),
+            Vec::<Solution>::new()
+        );
+        assert_eq!(
+            evaluator
+                .ask("Who is the sender of what to Bob?")
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
+
+#[test]
+fn evaluate_actions() {
+    let source = "Alice likes Bob. Carol likes Bob. Bob likes Carol. Bob is famous.
+        X admires Y if X likes Y and Y is famous.";
+
+    for strategy in [Strategy::TopDown, Strategy::BottomUp] {
+        let mut evaluator = Evaluator::with_strategy(strategy);
+        evaluator.load(source).unwrap();
+
+        let mut admirers = evaluator.ask("Who admires Bob?").unwrap();
+        admirers.sort_by(|left, right| left["who"].cmp(&right["who"]));
+        assert_eq!(
+            admirers,
+            vec![
+                Solution::from([("who".to_string(), "alice".to_string())]),
+                Solution::from([("who".to_string(), "carol".to_string())]),
+            ]
+        );
+        assert_eq!(
+            evaluator.ask("Bob admires who?").unwrap(),
+            Vec::<Solution>::new()
+        );
+    }
+}
+
+#[test]
+fn evaluate_quantified() {
+    let source = "All hamsters are mammals. Every mammal is warm-blooded. Sam is a hamster.
+        Every parent of X is an ancestor of X. Ann is the parent of Bob.";
+
+    for strategy in [Strategy::TopDown, Strategy::BottomUp] {
+        let mut evaluator = Evaluator::with_strategy(strategy);
+        evaluator.load(source).unwrap();
+
+        assert_eq!(
+            evaluator.ask("Is Sam warm-blooded?").unwrap(),
+            vec![Solution::new()]
+        );
+        assert_eq!(
+            evaluator.ask("Who is an ancestor of Bob?").unwrap(),
+            vec![Solution::from([("who".to_string(), "ann".to_string())])]
+        );
+    }
+}
+
+#[test]
+fn evaluate_query_types() {
+    let source = "Bob is a child of Alice. Carol is a child of Alice. Carol is a child of Alice.
+        Sam is a hamster. X is a mammal if X is a hamster.";
+
+    for strategy in [Strategy::TopDown, Strategy::BottomUp] {
+        let mut evaluator = Evaluator::with_strategy(strategy);
+        evaluator.load(source).unwrap();
+
+        assert_eq!(
+            evaluator.ask("Is there a mammal?").unwrap(),
+            vec![Solution::new()]
+        );
+        assert_eq!(
+            evaluator
+                .ask("Is there anyone who is a child of Bob?")
+                .unwrap(),
+            Vec::<Solution>::new()
+        );
+        assert_eq!(
+            evaluator.ask("How many children does Alice have?").unwrap(),
+            vec![Solution::from([("how many".to_string(), "2".to_string())])]
+        );
+        assert_eq!(
+            evaluator.ask("How many children does Bob have?").unwrap(),
+            vec![Solution::from([("how many".to_string(), "0".to_string())])]
+        );
+        assert_eq!(
+            evaluator.ask("List all mammals.").unwrap(),
+            vec![Solution::from([("mammals".to_string(), "sam".to_string())])]
+        );
+    }
+}
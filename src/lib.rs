@@ -1,17 +1,103 @@
 use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
     fs::File,
     io::{self, prelude::*},
     path::Path,
 };
 
+#[cfg(feature = "swipl")]
 mod communicator;
+#[cfg(feature = "native")]
+pub mod evaluator;
 mod parser;
 mod scanner;
+mod stratification;
 mod transpiler;
 
-use transpiler::Identifiers;
+#[cfg(feature = "swipl")]
+pub use communicator::{
+    load_prolog, query_prolog, query_solutions, start_prolog, Session, SessionError, Solutions,
+};
+#[cfg(feature = "native")]
+pub use evaluator::{EvaluationError, Evaluator, Strategy};
+pub use parser::ast::QueryType;
+pub use parser::{Location, ParseError};
+pub use stratification::StratificationError;
+#[cfg(fuzzing)]
+#[doc(hidden)]
+pub use transpiler::syntax::check_prolog;
+pub use transpiler::{IdentifierError, Identifiers, Naming, Query, SourceMap};
+
+/// A single solution to a query, mapping the Co-log name of each variable or pronoun in the query to the Co-log name of the literal it was bound to.
+pub type Solution = HashMap<String, String>;
+
+/// An error raised while transpiling Co-log source to Prolog.
+#[derive(Debug, Clone)]
+pub enum TranspileError {
+    /// The Co-log source could not be parsed.
+    Parse(ParseError),
+
+    /// A relationship depends on its own negation, so Prolog could give wrong answers or never terminate.
+    Unstratifiable(StratificationError),
+}
+
+impl fmt::Display for TranspileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranspileError::Parse(err) => write!(f, "{err}"),
+            TranspileError::Unstratifiable(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for TranspileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TranspileError::Parse(err) => Some(err),
+            TranspileError::Unstratifiable(err) => Some(err),
+        }
+    }
+}
+
+impl From<ParseError> for TranspileError {
+    fn from(value: ParseError) -> Self {
+        TranspileError::Parse(value)
+    }
+}
+
+impl From<StratificationError> for TranspileError {
+    fn from(value: StratificationError) -> Self {
+        TranspileError::Unstratifiable(value)
+    }
+}
+
+/// Combines the solutions to a query into the answer that it asks for.
+/// A count is given as a single solution, binding 'how many' to the number of distinct solutions.
+#[cfg(any(feature = "swipl", feature = "native"))]
+fn answer(kind: QueryType, solutions: Vec<Solution>) -> Vec<Solution> {
+    if kind == QueryType::Solutions {
+        return solutions;
+    }
 
-pub use communicator::{query_prolog, start_prolog};
+    let mut distinct = Vec::new();
+    for solution in solutions {
+        if !distinct.contains(&solution) {
+            distinct.push(solution);
+        }
+    }
+
+    match kind {
+        QueryType::Exists if distinct.is_empty() => vec![],
+        QueryType::Exists => vec![Solution::new()],
+        QueryType::Count => vec![Solution::from([(
+            "how many".to_string(),
+            distinct.len().to_string(),
+        )])],
+        _ => distinct,
+    }
+}
 
 /// Remove the '\\?\' prefix that Windows sometimes adds to paths.
 pub fn remove_path_prefix(s: &str) -> &str {
@@ -33,24 +119,66 @@ pub fn read_file(path: &str) -> io::Result<String> {
 }
 
 /// Transpile a given source string to Prolog.
-/// Returns the generated code, any queries included in the source string, and a table to translate identifiers from Prolog to co-log.
+/// Returns the generated code, any queries included in the source string, a table to translate identifiers from Prolog to co-log,
+/// and a source map with the clauses of the generated code and the statement that each of them came from.
+/// Returns an error if the source can't be parsed, or if a relationship depends on its own negation.
 pub fn transpile(
     source: String,
     identifiers: Option<Identifiers>,
-) -> Result<(String, Vec<transpiler::Query>, transpiler::Identifiers), parser::ParseError> {
-    let tokens = scanner::scan(&source)?;
-    //dbg!(&tokens);
-    let trees = parser::parse(&tokens)?;
-    //dbg!(&trees);
+) -> Result<
+    (
+        String,
+        Vec<transpiler::Query>,
+        transpiler::Identifiers,
+        SourceMap,
+    ),
+    TranspileError,
+> {
+    let trees = parser::parse_source(&source)?;
+    stratification::stratify(&stratification::rules(&source, &trees))?;
+
     Ok(transpiler::transpile(trees, identifiers))
 }
 
+/// Checks a given source string for errors.
+/// Unlike the other functions, this doesn't stop at the first error, so it returns every parse error in the source string.
+pub fn check(source: &str) -> Vec<ParseError> {
+    let (_, errors) = parser::parse_source_recovering(source);
+    errors
+}
+
+/// Parses a source string, returning its first query.
+/// Returns an error if the source string does not contain a query.
+fn parse_query(source: &str) -> Result<parser::ast::Stmt, parser::ParseError> {
+    let trees = parser::parse_source(source)?;
+
+    match trees
+        .into_iter()
+        .find(|tree| matches!(tree.kind(), parser::ast::StmtType::Query(_)))
+    {
+        Some(query) => Ok(query),
+        None => {
+            let tokens = scanner::scan(source)?;
+            let token = tokens
+                .iter()
+                .find(|token| token.is_terminator())
+                .unwrap_or(&tokens[tokens.len() - 1]);
+            Err(
+                parser::ParseError::new(token.clone(), scanner::TokenType::QuestionMark)
+                    .locate(source),
+            )
+        }
+    }
+}
+
 /// Transpiles a given source string to Prolog, returning a single query.
+/// Returns an error if the source string does not contain a query.
 pub fn transpile_query(
     source: String,
     identifiers: Option<Identifiers>,
 ) -> Result<(transpiler::Query, Identifiers), parser::ParseError> {
-    let (_, queries, identifiers) = transpile(source, identifiers)?;
+    let query = parse_query(&source)?;
+    let (_, mut queries, identifiers, _) = transpiler::transpile(vec![query], identifiers);
 
-    Ok((queries[0].clone(), identifiers))
+    Ok((queries.remove(0), identifiers))
 }
@@ -1,4 +1,6 @@
-use crate::scanner::{Token, TokenType};
+use std::{fmt, hash::Hash, ops::Range};
+
+use crate::scanner::{self, Token, TokenType};
 
 /// The type of term that an identifier represents.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -6,6 +8,73 @@ pub enum IdenType {
     Literal,
     Variable,
     Pronoun,
+    Number,
+    /// A verb other than 'is' or 'are', such as 'likes', which can only name a relationship.
+    Verb,
+    /// A plural noun, such as 'mammals' in `All hamsters are mammals.`, which names the relationship of its singular form.
+    Plural,
+}
+
+/// A comparison that is built in to Co-log.
+/// In a clause, it is represented as a relationship named by the comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Comparison {
+    /// `X is Y`, which checks whether two terms are identical.
+    Eq,
+    /// `X is greater than Y`
+    Gt,
+    /// `X is less than Y`
+    Lt,
+    /// `X is at least Y`
+    Ge,
+    /// `X is at most Y`
+    Le,
+}
+
+impl Comparison {
+    pub const ALL: [Comparison; 5] = [
+        Comparison::Eq,
+        Comparison::Gt,
+        Comparison::Lt,
+        Comparison::Ge,
+        Comparison::Le,
+    ];
+
+    /// The name of the relationship that represents the comparison.
+    pub fn name(self) -> &'static str {
+        match self {
+            Comparison::Eq => "eq",
+            Comparison::Gt => "gt",
+            Comparison::Lt => "lt",
+            Comparison::Ge => "ge",
+            Comparison::Le => "le",
+        }
+    }
+
+    /// The words that come between the verb and the right hand identifier in Co-log.
+    pub fn phrase(self) -> &'static [&'static str] {
+        match self {
+            Comparison::Eq => &[],
+            Comparison::Gt => &["greater", "than"],
+            Comparison::Lt => &["less", "than"],
+            Comparison::Ge => &["at", "least"],
+            Comparison::Le => &["at", "most"],
+        }
+    }
+
+    /// Gets the comparison represented by a binary relationship, if it is one.
+    pub fn from_relationship(relationship: &Identifier, right: &[Identifier]) -> Option<Self> {
+        if right.len() != 1
+            || relationship.kind != IdenType::Literal
+            || relationship.preposition.is_some()
+        {
+            return None;
+        }
+
+        Comparison::ALL
+            .into_iter()
+            .find(|comparison| comparison.name() == relationship.lexeme)
+    }
 }
 
 /// A literal, variable, or pronoun.
@@ -33,6 +102,158 @@ impl Identifier {
     pub fn preposition(&self) -> &Option<String> {
         &self.preposition
     }
+
+    /// The name that a literal, verb or plural noun is known by, in lowercase.
+    /// A plural noun is known by its singular form, so that 'mammals' and 'mammal' name the same relationship.
+    pub(crate) fn name(&self) -> String {
+        let name = self.lexeme.to_lowercase();
+        match self.kind {
+            IdenType::Plural => singular(&name),
+            _ => name,
+        }
+    }
+
+    /// Creates the hidden variable with the given number.
+    pub(super) fn hidden(number: usize) -> Self {
+        Identifier {
+            kind: IdenType::Variable,
+            lexeme: format!("{HIDDEN_VARIABLE}{number}"),
+            article: None,
+            preposition: None,
+        }
+    }
+
+    /// Checks if the identifier is a hidden variable, standing for a described value.
+    fn is_hidden(&self) -> bool {
+        self.kind == IdenType::Variable
+            && self
+                .lexeme
+                .strip_prefix(HIDDEN_VARIABLE)
+                .is_some_and(|number| number.parse::<usize>().is_ok())
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(article) = &self.article {
+            write!(f, "{article} ")?;
+        }
+        // Names that wouldn't be scanned back as the same identifier, such as 'new york' or 'New  York', need quotes
+        let kind = match self.kind {
+            IdenType::Literal | IdenType::Verb | IdenType::Plural => TokenType::Literal,
+            IdenType::Variable => TokenType::Variable,
+            IdenType::Pronoun => TokenType::Pronoun,
+            IdenType::Number => TokenType::Number,
+        };
+        match scanner::tokenise(&self.lexeme).as_slice() {
+            [token, _]
+                if token.kind() == kind
+                    && Identifier::try_from(token)
+                        .is_ok_and(|identifier| identifier.lexeme == self.lexeme) =>
+            {
+                write!(f, "{}", self.lexeme)?
+            }
+            _ => write!(
+                f,
+                "\"{}\"",
+                self.lexeme.replace('\\', "\\\\").replace('"', "\\\"")
+            )?,
+        }
+        if let Some(preposition) = &self.preposition {
+            write!(f, " {preposition}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Removes the backslashes from the escape sequences in a quoted name.
+fn unescape(quoted: &str) -> String {
+    let mut output = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => output.extend(chars.next()),
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+impl From<Comparison> for Identifier {
+    fn from(value: Comparison) -> Self {
+        Identifier {
+            kind: IdenType::Literal,
+            lexeme: value.name().to_string(),
+            article: None,
+            preposition: None,
+        }
+    }
+}
+
+/// The name of the variable that a quantified statement, such as `All hamsters are mammals`, is about.
+/// It can't be written as a variable in Co-log, so it never clashes with the statement's other variables.
+pub(crate) const QUANTIFIED_VARIABLE: &str = "_";
+
+/// The prefix of the hidden variables that stand for described values, as in `the age of X is greater than 17`, which are numbered from 1 within a statement.
+/// Like the quantified variable, they can't be written in Co-log.
+const HIDDEN_VARIABLE: &str = "_";
+
+/// Gets the singular form of a name whose last word may be a regular English plural, such as the plural noun of a quantified statement.
+/// Words ending in 'ss', 'us' or 'is', such as 'glass' and 'bus', are left alone.
+pub(crate) fn singular(name: &str) -> String {
+    const IRREGULAR: [(&str, &str); 8] = [
+        ("children", "child"),
+        ("people", "person"),
+        ("men", "man"),
+        ("women", "woman"),
+        ("mice", "mouse"),
+        ("geese", "goose"),
+        ("feet", "foot"),
+        ("teeth", "tooth"),
+    ];
+
+    let (start, word) = name.split_at(name.rfind(' ').map_or(0, |i| i + 1));
+    let word = if let Some(&(_, singular)) = IRREGULAR.iter().find(|&&(plural, _)| plural == word) {
+        singular.to_string()
+    } else if let Some(stem) = word.strip_suffix("ies").filter(|stem| stem.len() > 1) {
+        format!("{stem}y")
+    } else if ["sses", "shes", "ches", "xes", "zes"]
+        .iter()
+        .any(|suffix| word.ends_with(suffix))
+    {
+        word[..word.len() - 2].to_string()
+    } else if word.len() > 2
+        && word.ends_with('s')
+        && !["ss", "us", "is"]
+            .iter()
+            .any(|suffix| word.ends_with(suffix))
+    {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    };
+
+    format!("{start}{word}")
+}
+
+/// Writes a number in the form Prolog uses, without leading zeros or unnecessary trailing zeros, so that equal numbers have the same name.
+fn canonical_number(number: &str) -> String {
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let integer = match integer.trim_start_matches('0') {
+        "" => "0",
+        integer => integer,
+    };
+
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        match fraction.trim_end_matches('0') {
+            "" => format!("{integer}.0"),
+            fraction => format!("{integer}.{fraction}"),
+        }
+    }
 }
 
 impl TryFrom<&Token> for Identifier {
@@ -46,24 +267,54 @@ impl TryFrom<&Token> for Identifier {
             IdenType::Variable
         } else if value.kind() == TokenType::Pronoun {
             IdenType::Pronoun
+        } else if value.kind() == TokenType::Number {
+            IdenType::Number
         } else {
             return Err(super::ParseError {
                 token: value.clone(),
-                expected: Vec::from([TokenType::Literal, TokenType::Variable]),
+                expected: Vec::from([TokenType::Literal, TokenType::Variable, TokenType::Number]),
+                problem: super::Problem::Unexpected,
+                context: None,
             });
         };
 
-        return Ok(Identifier {
-            lexeme: value.lexeme().to_string(),
+        let lexeme = value.lexeme();
+        let lexeme = match lexeme.strip_prefix('"') {
+            _ if kind == IdenType::Number => canonical_number(lexeme),
+            Some(quoted) => unescape(quoted.strip_suffix('"').unwrap_or(quoted)),
+            // The words of a proper name may be separated by any amount of whitespace
+            None => lexeme.split_whitespace().collect::<Vec<_>>().join(" "),
+        };
+
+        // A literal such as 'the gt of' or 'gts' would otherwise become the comparison, as relationships are found by name
+        if kind == IdenType::Literal {
+            let name = lexeme.to_lowercase();
+            let plural = lexeme
+                .starts_with(char::is_lowercase)
+                .then(|| singular(&name));
+            if Comparison::ALL.iter().any(|comparison| {
+                comparison.name() == name || plural.as_deref() == Some(comparison.name())
+            }) {
+                return Err(super::ParseError {
+                    token: value.clone(),
+                    expected: vec![],
+                    problem: super::Problem::Reserved,
+                    context: None,
+                });
+            }
+        }
+
+        Ok(Identifier {
+            lexeme,
             article: None,
             preposition: None,
             kind,
-        });
+        })
     }
 }
 
 /// The function of an operator.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum OperatorType {
     And,
     Or,
@@ -71,39 +322,262 @@ pub enum OperatorType {
 
 /// A clause in a rule. Note that clauses of the form `'(' clause ')'` have no special representation, as the parentheses simply change the order of the parsing.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[allow(clippy::large_enum_variant)]
 pub enum Clause {
-    /// A clause of the form `article? identifier verb ‘not’? article? literal (preposition article? identifier)?.`
+    /// A clause of the form `article? identifier verb ‘not’? article? literal (preposition article? identifier)*.`
+    /// or `article? identifier literal (article? identifier)? (preposition article? identifier)*`, where the literal is a verb such as 'likes'.
+    /// The identifiers after the relationship are its right hand identifiers, in order.
     Simple {
         negated: bool,
         left: Identifier,
         relationship: Identifier,
-        right: Option<Identifier>,
+        right: Vec<Identifier>,
     },
 
-    /// A clause of the form `clause operator clause`.
+    /// A clause of the form `clause (operator clause)+`, where every operator is the same.
+    /// Operators are right associative, so a chain of clauses that changes operator, as in `A and B or C`, nests the rest of the chain as its last clause.
     Operator {
         op_type: OperatorType,
-        left: Box<Clause>,
-        right: Box<Clause>,
+        clauses: Vec<Clause>,
     },
 }
 
+impl Clause {
+    /// Joins a chain of clauses with the operators between them, grouping the clauses that each operator joins without recursion.
+    /// There must be one fewer operator than clauses.
+    pub(super) fn chain(mut clauses: Vec<Clause>, operators: Vec<OperatorType>) -> Clause {
+        let mut group = vec![clauses.pop().expect("a chain has at least one clause")];
+        let mut current = None;
+
+        // Work from the right, as each change of operator nests everything after it
+        for (op_type, clause) in operators.into_iter().rev().zip(clauses.into_iter().rev()) {
+            if let Some(current) = current.filter(|current| *current != op_type) {
+                group.reverse();
+                group = vec![Clause::Operator {
+                    op_type: current,
+                    clauses: group,
+                }];
+            }
+            current = Some(op_type);
+            group.push(clause);
+        }
+
+        match current {
+            Some(op_type) => {
+                group.reverse();
+                Clause::Operator {
+                    op_type,
+                    clauses: group,
+                }
+            }
+            None => group.pop().unwrap(),
+        }
+    }
+}
+
+/// Writes a simple clause or statement in Co-log, in the form `left verb 'not'? relationship right*`.
+/// A relationship that is itself a verb, as in `Alice likes Bob`, replaces 'verb', and can't be negated.
+fn write_simple(
+    f: &mut fmt::Formatter<'_>,
+    verb: &str,
+    negated: bool,
+    left: &Identifier,
+    relationship: &Identifier,
+    right: &[Identifier],
+) -> fmt::Result {
+    if relationship.kind == IdenType::Verb {
+        write!(f, "{left} ")?;
+    } else {
+        write!(f, "{left} {verb} ")?;
+    }
+    if negated {
+        write!(f, "not ")?;
+    }
+
+    write_relationship(f, relationship, right)
+}
+
+/// Writes the relationship of a simple clause or statement, and its right hand identifiers, in the form they are written after the verb.
+fn write_relationship(
+    f: &mut fmt::Formatter<'_>,
+    relationship: &Identifier,
+    right: &[Identifier],
+) -> fmt::Result {
+    match (right, Comparison::from_relationship(relationship, right)) {
+        // `X is not? Y` and the other comparisons are represented as builtin relationships
+        ([right], Some(comparison)) => {
+            for word in comparison.phrase() {
+                write!(f, "{word} ")?;
+            }
+            write!(f, "{right}")
+        }
+        // A measure, as in `X is 34 years old`
+        ([right], None)
+            if relationship.kind != IdenType::Verb && relationship.preposition.is_none() =>
+        {
+            write!(f, "{right} {}", relationship.lexeme)
+        }
+        _ => {
+            write!(f, "{relationship}")?;
+            // Each identifier is written with the preposition after it, and identifiers without one are joined with 'and'
+            for (i, identifier) in right.iter().enumerate() {
+                if i > 0 && right[i - 1].preposition.is_none() {
+                    write!(f, " and")?;
+                }
+                write!(f, " {identifier}")?;
+            }
+
+            Ok(())
+        }
+    }
+}
+
+impl Clause {
+    /// Gets the parts of a comparison about a described value, as in `the age of X is greater than 17`, if the clause is one.
+    /// Returns the description's relationship and right hand identifiers, and the comparison about its value.
+    fn description(&self) -> Option<(&Identifier, &[Identifier], &Clause)> {
+        let Clause::Operator {
+            op_type: OperatorType::And,
+            clauses,
+        } = self
+        else {
+            return None;
+        };
+        let [Clause::Simple {
+            negated: false,
+            left,
+            relationship,
+            right,
+        }, comparison @ Clause::Simple {
+            left: value,
+            relationship: compared,
+            right: other,
+            ..
+        }] = clauses.as_slice()
+        else {
+            return None;
+        };
+
+        (left.is_hidden()
+            && value == left
+            && relationship.kind == IdenType::Literal
+            && relationship.preposition.is_some()
+            && Comparison::from_relationship(compared, other).is_some())
+        .then_some((relationship, right.as_slice(), comparison))
+    }
+}
+
+impl fmt::Display for Clause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((relationship, right, comparison)) = self.description() {
+            let Clause::Simple {
+                negated,
+                relationship: compared,
+                right: value,
+                ..
+            } = comparison
+            else {
+                unreachable!("the comparison about a described value is a simple clause")
+            };
+
+            write_relationship(f, relationship, right)?;
+            write!(f, " is ")?;
+            if *negated {
+                write!(f, "not ")?;
+            }
+            return write_relationship(f, compared, value);
+        }
+
+        match self {
+            Clause::Simple {
+                negated,
+                left,
+                relationship,
+                right,
+            } => write_simple(f, "is", *negated, left, relationship, right),
+            Clause::Operator { op_type, clauses } => {
+                let operator = match op_type {
+                    OperatorType::And => "and",
+                    OperatorType::Or => "or",
+                };
+                // Operators are right associative, so only the last clause can be a chain of different operators without parentheses
+                for (i, clause) in clauses.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " {operator} ")?;
+                    }
+                    match clause {
+                        Clause::Operator { op_type: inner, .. }
+                            if clause.description().is_none()
+                                && (i + 1 < clauses.len() || inner == op_type) =>
+                        {
+                            write!(f, "({clause})")?
+                        }
+                        _ => write!(f, "{clause}")?,
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
 /// The type of a statement.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StmtType {
     Fact,
     Rule,
-    Query,
+    Query(QueryType),
+}
+
+/// The kind of answer that a query asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryType {
+    /// Every solution, as in `Who is the brother of Jack?`
+    Solutions,
+    /// Whether there is any solution, as in `Is there a mammal?`
+    Exists,
+    /// The number of distinct solutions, as in `How many children does Alice have?`
+    Count,
+    /// Every distinct solution, as in `List all mammals.`
+    List,
 }
 
 /// A statement, terminated with a full stop or a question mark.
-#[derive(Debug, PartialEq, Eq, Hash)]
+/// Statements are equal if they say the same thing, wherever they are in the source.
+#[derive(Debug, Clone, Eq)]
 pub struct Stmt {
     pub(super) kind: StmtType,
     pub(super) left: Identifier,
     pub(super) relationship: Identifier,
-    pub(super) right: Option<Identifier>,
+    pub(super) right: Vec<Identifier>,
+    /// The quantifier of a quantified statement, such as `All hamsters are mammals.`, which is a rule about a fresh variable.
+    pub(super) quantifier: Option<String>,
     pub(super) condition: Option<Clause>,
+    /// The byte range of the statement in the source it was parsed from, including its terminator.
+    pub(super) span: Range<usize>,
+}
+
+impl PartialEq for Stmt {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.left == other.left
+            && self.relationship == other.relationship
+            && self.right == other.right
+            && self.quantifier == other.quantifier
+            && self.condition == other.condition
+    }
+}
+
+impl Hash for Stmt {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.kind.hash(state);
+        self.left.hash(state);
+        self.relationship.hash(state);
+        self.right.hash(state);
+        self.quantifier.hash(state);
+        self.condition.hash(state);
+    }
 }
 
 impl Stmt {
@@ -119,11 +593,92 @@ impl Stmt {
         &self.relationship
     }
 
-    pub fn right(&self) -> &Option<Identifier> {
+    /// The identifiers after the relationship, in order.
+    pub fn right(&self) -> &[Identifier] {
         &self.right
     }
 
     pub fn condition(&self) -> &Option<Clause> {
         &self.condition
     }
+
+    /// The byte range of the statement in the source it was parsed from, including its terminator.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Gives the statement the byte range that it was parsed from.
+    pub(super) fn spanning(mut self, span: Range<usize>) -> Self {
+        self.span = span;
+        self
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.kind, &self.quantifier, &self.condition) {
+            // The condition of a quantified statement describes its variable with the quantified noun
+            (
+                StmtType::Rule,
+                Some(quantifier),
+                Some(Clause::Simple {
+                    relationship: noun,
+                    right: noun_right,
+                    ..
+                }),
+            ) => {
+                write!(f, "{quantifier} ")?;
+                write_relationship(f, noun, noun_right)?;
+                if self.relationship.kind != IdenType::Verb {
+                    let verb = if quantifier.eq_ignore_ascii_case("all") {
+                        "are"
+                    } else {
+                        "is"
+                    };
+                    write!(f, " {verb}")?;
+                }
+                write!(f, " ")?;
+                write_relationship(f, &self.relationship, &self.right)?;
+                write!(f, ".")
+            }
+            (StmtType::Query(QueryType::Exists), ..) => {
+                write!(f, "Is there ")?;
+                if self.relationship.kind == IdenType::Verb {
+                    write!(f, "anyone who ")?;
+                } else if self.relationship.article.is_none() {
+                    write!(f, "anyone who is ")?;
+                }
+                write_relationship(f, &self.relationship, &self.right)?;
+                write!(f, "?")
+            }
+            (StmtType::Query(QueryType::Count), ..) => match self.right.as_slice() {
+                [owner] => write!(f, "How many {} does {owner} have?", self.relationship),
+                _ => write!(f, "How many {} are there?", self.relationship),
+            },
+            (StmtType::Query(QueryType::List), ..) => {
+                write!(f, "List all ")?;
+                write_relationship(f, &self.relationship, &self.right)?;
+                write!(f, ".")
+            }
+            // Questions about a verb are written in the same order as facts, as in `Who likes Bob?`
+            (StmtType::Query(QueryType::Solutions), ..)
+                if self.relationship.kind == IdenType::Verb =>
+            {
+                write_simple(f, "is", false, &self.left, &self.relationship, &self.right)?;
+                write!(f, "?")
+            }
+            (StmtType::Query(QueryType::Solutions), ..) => {
+                write!(f, "Is {} ", self.left)?;
+                write_relationship(f, &self.relationship, &self.right)?;
+                write!(f, "?")
+            }
+            (StmtType::Fact | StmtType::Rule, ..) => {
+                write_simple(f, "is", false, &self.left, &self.relationship, &self.right)?;
+                if let Some(condition) = &self.condition {
+                    write!(f, " if {condition}")?;
+                }
+                write!(f, ".")
+            }
+        }
+    }
 }
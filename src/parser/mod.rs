@@ -1,44 +1,186 @@
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, ops::Range};
 
-use super::scanner::{Token, TokenType};
+use tracing::{debug, debug_span, trace};
+
+use super::scanner::{self, Token, TokenType};
 
 pub mod ast;
 
-#[derive(Debug)]
+/// The maximum depth that clauses can be nested inside each other, with parentheses or by changing operator, so that later passes over a clause can't overflow the stack.
+const MAX_NESTING: usize = 100;
+
+/// A position in Co-log source, where the first line and the first column are both 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    /// Finds the line and column of a byte offset in the source.
+    pub(crate) fn of(source: &str, offset: usize) -> Self {
+        let mut offset = offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        Location {
+            line: source[..offset].matches('\n').count() + 1,
+            column: source[line_start..offset].chars().count() + 1,
+        }
+    }
+
+    /// Finds the line and column of each of a sequence of byte offsets in the source, reading the source only once if the offsets are in order.
+    pub(crate) fn of_each(source: &str, offsets: impl IntoIterator<Item = usize>) -> Vec<Self> {
+        let mut location = Location { line: 1, column: 1 };
+        let mut position = 0;
+
+        offsets
+            .into_iter()
+            .map(|offset| {
+                let mut offset = offset.min(source.len());
+                while !source.is_char_boundary(offset) {
+                    offset -= 1;
+                }
+                if offset < position {
+                    location = Location { line: 1, column: 1 };
+                    position = 0;
+                }
+
+                for c in source[position..offset].chars() {
+                    if c == '\n' {
+                        location.line += 1;
+                        location.column = 1;
+                    } else {
+                        location.column += 1;
+                    }
+                }
+                position = offset;
+                location
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// The location of an error, with the line of source that it is on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Context {
+    location: Location,
+    line: String,
+}
+
+/// What was wrong with the token that an error refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Problem {
+    /// The token wasn't one of the expected kinds.
+    Unexpected,
+    /// The token nested a clause more deeply than the parser allows.
+    TooDeep,
+    /// The token is a literal named after one of the builtin comparisons, which would be mistaken for it.
+    Reserved,
+}
+
+#[derive(Debug, Clone)]
 pub struct ParseError {
     token: Token,
     expected: Vec<TokenType>,
+    problem: Problem,
+    context: Option<Context>,
 }
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.expected.is_empty() {
-            write!(f, "unexpected character: {}", self.token.lexeme(),)
-        } else if self.expected.len() == 1 {
-            write!(
+        if let Some(context) = &self.context {
+            write!(f, "{}: ", context.location)?;
+        }
+
+        match self.expected.as_slice() {
+            _ if self.problem == Problem::TooDeep => write!(
                 f,
-                "expected {}, found {}",
-                self.expected[0],
-                self.token.kind(),
-            )
-        } else {
-            write!(
+                "clauses are nested more than {MAX_NESTING} deep, found {}",
+                self.token.kind()
+            ),
+            _ if self.problem == Problem::Reserved => write!(
                 f,
-                "expected one of {:#?}, found {}",
-                self.expected,
-                self.token.kind(),
-            )
+                "'{}' is reserved for a builtin comparison",
+                self.token.lexeme()
+            ),
+            [] if self.token.lexeme().starts_with('"') => write!(f, "unterminated quoted name"),
+            [] => write!(f, "unexpected character: {}", self.token.lexeme()),
+            [expected] => write!(f, "expected {expected}, found {}", self.token.kind()),
+            [expected @ .., last] => {
+                let expected: Vec<_> = expected.iter().map(ToString::to_string).collect();
+                write!(
+                    f,
+                    "expected {} or {last}, found {}",
+                    expected.join(", "),
+                    self.token.kind()
+                )
+            }
         }
     }
 }
 
 impl ParseError {
-    fn new(token: Token, expected: TokenType) -> Self {
+    pub(crate) fn new(token: Token, expected: TokenType) -> Self {
         Self {
             token,
             expected: vec![expected],
+            problem: Problem::Unexpected,
+            context: None,
         }
     }
+
+    /// The byte range of the source that the error refers to.
+    pub fn span(&self) -> Range<usize> {
+        self.token.start()..self.token.start() + self.token.lexeme().len()
+    }
+
+    /// The line and column that the error is at, if the error has been given its source.
+    pub fn location(&self) -> Option<Location> {
+        self.context.as_ref().map(|context| context.location)
+    }
+
+    /// Finds the location of the error in the source it came from.
+    pub(crate) fn locate(mut self, source: &str) -> Self {
+        let mut start = self.token.start().min(source.len());
+        while !source.is_char_boundary(start) {
+            start -= 1;
+        }
+
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+
+        self.context = Some(Context {
+            location: Location::of(source, start),
+            line: source[line_start..line_end].trim_end().to_string(),
+        });
+        self
+    }
+
+    /// Renders the error with the line of source that it is on, underlining the part of the line that caused it.
+    /// If the error has not been given its source, only the message is rendered.
+    pub fn render(&self) -> String {
+        let Some(Context { location, line }) = &self.context else {
+            return self.to_string();
+        };
+
+        let number = location.line.to_string();
+        let padding = " ".repeat(number.len());
+        let indent = " ".repeat(location.column - 1);
+        let underline = "^".repeat(self.token.lexeme().chars().count().max(1));
+
+        format!("error: {self}\n{padding} |\n{number} | {line}\n{padding} | {indent}{underline}")
+    }
 }
 
 impl From<Token> for ParseError {
@@ -46,364 +188,833 @@ impl From<Token> for ParseError {
         Self {
             token: value,
             expected: vec![],
+            problem: Problem::Unexpected,
+            context: None,
         }
     }
 }
 
 impl Error for ParseError {}
 
-/// Finds the next non-parenthesised operator.
-/// Returns the index of the operator if one is found, and None otherwise.
-fn find_unwrapped_operator(tokens: &[Token]) -> Option<usize> {
-    let mut open_paren = 0;
-    let mut i = 0;
-
-    while i < tokens.len() {
-        match &tokens[i].kind() {
-            TokenType::Operator => {
-                //dbg!(open_paren);
-                if open_paren == 0 {
-                    return Some(i);
-                }
-            }
-            TokenType::LeftParen => open_paren += 1,
-            TokenType::RightParen => open_paren -= 1,
-            _ => {}
+/// A recursive descent parser for a sequence of tokens.
+///
+/// Statements follow this grammar, where each `identifier` may be a literal, a variable, a pronoun or a number:
+/// ```text
+/// statement    → article? identifier predicate others? ('if' clause)? ('.' | '?')
+///              | verb article? identifier (comparison | relationship) '?'
+///              | quantifier article? literal arguments? predicate others? '.'
+///              | verb 'there' (relationship | ('anyone' | 'anything' | 'someone' | 'something') pronoun predicate) '?'
+///              | 'how' 'many' article? literal (('does' | 'do') article? identifier 'have' | verb 'there') '?'
+///              | 'list' quantifier article? literal arguments? ('.' | '?')
+/// predicate    → verb relationship | action
+/// relationship → article? literal arguments?
+///              | measure
+/// action       → literal (article? identifier more* | arguments?)
+/// arguments    → preposition article? identifier more*
+/// more         → (preposition | 'and') article? identifier
+/// others       → ('and' article? identifier)+
+/// measure      → (number | variable | pronoun) literal+
+/// clause       → operand (operator operand)*
+/// operand      → '(' clause ')' | simple
+/// simple       → article? identifier (verb 'not'? (comparison | measure | article? identifier (arguments others?)?) | action others?)
+///              | article? literal arguments verb 'not'? (comparison | number | variable | pronoun)
+/// quantifier   → 'all' | 'every' | 'each'
+/// comparison   → ('greater' 'than' | 'less' 'than' | 'at' 'least' | 'at' 'most') article? identifier
+/// ```
+/// Operators all have the same precedence, and are right associative.
+/// Chains of clauses are parsed in a loop, but clauses can only be nested up to a fixed depth, counting each pair of parentheses and each change of operator.
+/// A simple clause whose relationship is a single variable or number, as in `X is not Y`, compares its two identifiers with 'eq'.
+/// A measure, as in `Alice is 34 years old`, is a binary relationship named by its literals, whose right hand identifier is its number.
+/// A simple clause about a described value, as in `the age of X is greater than 17`, is about a hidden variable, as in `V is the age of X and V is greater than 17`.
+/// The last three forms of statement are queries that ask whether there is any solution, how many distinct solutions there are, and what they are.
+/// They are about a hidden pronoun, which takes the place of their left hand identifier.
+/// A quantified statement, as in `Every parent of X is an ancestor of X`, is a rule about a fresh variable, on the condition that the quantified noun describes it.
+/// An action is a relationship named by a verb other than 'is' or 'are', as in `Alice likes Bob`, whose first argument can follow the verb directly.
+/// An 'and' only joins another argument to a relationship that follows 'the', as in `X is the sum of Y and Z`, which describes one value of all of its arguments.
+/// Anywhere else, an 'and' after the last argument lists other identifiers that the relationship holds for in its place, so `Alice is a friend of Bob and Carol` and `Ann likes Bob and Carol` each say two things.
+/// They become a statement, or a simple clause joined with 'and', for each identifier, and a query can't list identifiers.
+/// Either way, an 'and' is only part of the relationship if the identifier after it isn't followed by a verb or a literal, so that it can also join clauses.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    current: usize,
+    /// How deeply the clause being parsed is nested.
+    depth: usize,
+    /// The number of hidden variables in the statement being parsed.
+    hidden: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a parser for a non-empty sequence of tokens.
+    fn new(tokens: &'a [Token]) -> Self {
+        Parser {
+            tokens,
+            current: 0,
+            depth: 0,
+            hidden: 0,
         }
+    }
 
-        i += 1
+    /// Gets the next token, or the last token if every token has been consumed.
+    fn peek(&self) -> &'a Token {
+        &self.tokens[self.current.min(self.tokens.len() - 1)]
     }
 
-    None
-}
+    /// Consumes the next token and returns it.
+    fn advance(&mut self) -> &'a Token {
+        let token = self.peek();
+        if self.current < self.tokens.len() {
+            self.current += 1;
+        }
+
+        token
+    }
 
-/// Finds the next closing parenthesis before 'end'.
-/// Returns the index of the closing parenthesis if it is found, and None if it isn't.
-fn find_close(tokens: &[Token]) -> Option<usize> {
-    let mut i = 0;
-    while i < tokens.len() {
-        if tokens[i].kind() == TokenType::RightParen {
-            return Some(i);
+    /// Consumes the next token if it is of type 'kind'.
+    fn matches(&mut self, kind: TokenType) -> Option<&'a Token> {
+        if self.peek().kind() == kind {
+            Some(self.advance())
+        } else {
+            None
         }
+    }
 
-        i += 1
+    /// Consumes the next token, returning an error if it isn't of type 'kind'.
+    fn expect(&mut self, kind: TokenType) -> Result<&'a Token, ParseError> {
+        self.matches(kind)
+            .ok_or_else(|| ParseError::new(self.peek().clone(), kind))
     }
 
-    None
-}
+    /// Nests the clause being parsed one level deeper, returning an error for 'token' if it would be nested too deeply.
+    fn nest(&mut self, token: &Token) -> Result<(), ParseError> {
+        if self.depth >= MAX_NESTING {
+            return Err(ParseError {
+                token: token.clone(),
+                expected: vec![],
+                problem: Problem::TooDeep,
+                context: None,
+            });
+        }
 
-fn is_terminator(token: Option<&Token>) -> Option<bool> {
-    Some(token?.is_terminator())
-}
+        self.depth += 1;
+        Ok(())
+    }
 
-/// Remove the articles from a series of tokens, starting at index i and ending at a terminator.
-/// Returns a copy of the tokens with the articles stripped out, and a vec of articles, with positions 0, 1, and 2 referring to the left identifier, the relationship, and the right identifier respectively.
-fn collapse_articles(tokens: &[Token]) -> (Vec<Token>, Vec<Option<Token>>) {
-    let mut result = Vec::new();
-    let mut articles = Vec::new();
-    let mut article_found_on_last_iteration = false;
-
-    let mut i = 0;
-    loop {
-        if tokens[i].kind() == TokenType::Article {
-            articles.push(Some(tokens[i].clone()));
-            article_found_on_last_iteration = true
-        } else {
-            if [TokenType::Literal, TokenType::Variable, TokenType::Pronoun]
-                .contains(&tokens[i].kind())
-                && !article_found_on_last_iteration
-            {
-                articles.push(None)
-            }
+    /// Returns an error for the next token, which was expected to be one of 'expected'.
+    fn unexpected<T>(&self, expected: &[TokenType]) -> Result<T, ParseError> {
+        Err(ParseError {
+            token: self.peek().clone(),
+            expected: expected.to_vec(),
+            problem: Problem::Unexpected,
+            context: None,
+        })
+    }
 
-            result.push(tokens[i].clone());
-            article_found_on_last_iteration = false;
-        }
+    fn is_at_end(&self) -> bool {
+        self.current >= self.tokens.len() || self.peek().kind() == TokenType::Eof
+    }
 
-        i += 1;
-        if i >= tokens.len() || tokens[i - 1].is_terminator() {
-            break;
+    /// Skips past the next terminator, so that parsing can resume at the next statement.
+    fn synchronise(&mut self) {
+        while !self.is_at_end() {
+            if self.advance().is_terminator() {
+                return;
+            }
         }
     }
 
-    (result, articles)
-}
+    /// Parses an optional article, returning its lexeme.
+    fn article(&mut self) -> Option<String> {
+        self.matches(TokenType::Article)
+            .map(|article| article.lexeme().to_string())
+    }
 
-/// Check if a token of type 'kind' exists in tokens, returning false if a token of type 'stop_at' is found.
-fn type_between(tokens: &[Token], kind: TokenType, stop_at: TokenType) -> bool {
-    let mut i = 0;
+    /// Parses an identifier, which may be a literal, a variable or a pronoun, with an optional article.
+    fn identifier(&mut self) -> Result<ast::Identifier, ParseError> {
+        let article = self.article();
+        let mut identifier = ast::Identifier::try_from(self.peek())?;
+        self.advance();
 
-    while i < tokens.len() {
-        if tokens[i].kind() == kind {
-            return true;
-        } else if tokens[i].kind() == stop_at || tokens[i].is_terminator() {
-            return false;
-        }
-        i += 1
+        identifier.article = article;
+        Ok(identifier)
     }
 
-    false
-}
+    /// Parses a literal with an optional article.
+    fn literal(&mut self) -> Result<ast::Identifier, ParseError> {
+        let article = self.article();
+        let mut identifier = ast::Identifier::try_from(self.expect(TokenType::Literal)?)?;
 
-/// Parse 'tokens' into a clause, starting from 'i' and ending at 'end'.
-fn parse_clause(tokens: &[Token]) -> Result<ast::Clause, ParseError> {
-    //dbg!(tokens[i].start);
-    let (collapsed, articles) = collapse_articles(tokens);
+        identifier.article = article;
+        Ok(identifier)
+    }
 
-    // If the clause is of the form `clause op clause`
-    if let Some(op_index) = find_unwrapped_operator(tokens) {
-        let operator = &tokens[op_index];
-        //println!("found operator {:?}", operator);
+    /// Parses the literals that name the relationship in a measure, after its number or variable, which becomes the right hand identifier.
+    fn measure(
+        &mut self,
+        right: ast::Identifier,
+    ) -> Result<(ast::Identifier, Vec<ast::Identifier>), ParseError> {
+        let mut words = vec![ast::Identifier::try_from(self.expect(TokenType::Literal)?)?.lexeme];
+        while let Some(word) = self.matches(TokenType::Literal) {
+            words.push(ast::Identifier::try_from(word)?.lexeme);
+        }
+
+        let relationship = ast::Identifier {
+            kind: ast::IdenType::Literal,
+            lexeme: words.join(" "),
+            article: None,
+            preposition: None,
+        };
+        Ok((relationship, vec![right]))
+    }
 
-        //dbg!(&tokens[i..op_index], &tokens[(op_index + 1)..end]);
-        let left = Box::new(parse_clause(&tokens[..op_index])?);
-        let right = Box::new(parse_clause(&tokens[op_index + 1..])?);
+    /// Parses a comparison phrase, such as 'greater than', if there is one.
+    fn comparison(&mut self) -> Option<ast::Comparison> {
+        let words: Vec<_> = self.tokens[self.current.min(self.tokens.len())..]
+            .iter()
+            .take(2)
+            .map(|token| token.lexeme().to_lowercase())
+            .collect();
+
+        let comparison = ast::Comparison::ALL.into_iter().find(|comparison| {
+            !comparison.phrase().is_empty() && comparison.phrase() == words.as_slice()
+        })?;
+        self.current += comparison.phrase().len();
+        Some(comparison)
+    }
 
-        let op_type = if operator.lexeme().to_lowercase() == "and" {
-            ast::OperatorType::And
-        } else {
-            ast::OperatorType::Or
+    /// Determines whether the next token is an 'and' that joins another argument to a list, rather than another clause.
+    fn continues_arguments(&self) -> bool {
+        let mut tokens = self.tokens[self.current.min(self.tokens.len())..].iter();
+        if !tokens
+            .next()
+            .is_some_and(|token| token.lexeme().eq_ignore_ascii_case("and"))
+        {
+            return false;
+        }
+
+        let mut tokens = tokens.skip_while(|token| token.kind() == TokenType::Article);
+        let Some(first) = tokens.next() else {
+            return false;
         };
+        let Some(second) = tokens.next() else {
+            return first.is_identifier() || first.kind() == TokenType::Pronoun;
+        };
+        match second.kind() {
+            TokenType::Verb | TokenType::Literal => false,
+            // A literal with arguments followed by a verb starts a clause about a described value, such as `the age of X is 34`
+            TokenType::Prepostion if first.kind() == TokenType::Literal => !tokens
+                .find(|token| {
+                    !(token.is_identifier()
+                        || [
+                            TokenType::Prepostion,
+                            TokenType::Article,
+                            TokenType::Pronoun,
+                        ]
+                        .contains(&token.kind()))
+                })
+                .is_some_and(|token| token.kind() == TokenType::Verb),
+            _ => first.is_identifier() || first.kind() == TokenType::Pronoun,
+        }
+    }
 
-        return Ok(ast::Clause::Operator {
-            op_type,
-            left,
-            right,
-        });
+    /// Parses the right hand identifiers of a relationship, storing the preposition before each one in the relationship or the identifier before it.
+    fn arguments(
+        &mut self,
+        relationship: &mut ast::Identifier,
+    ) -> Result<Vec<ast::Identifier>, ParseError> {
+        let Some(preposition) = self.matches(TokenType::Prepostion) else {
+            return Ok(vec![]);
+        };
+        relationship.preposition = Some(preposition.lexeme().to_string());
+        let mut arguments = vec![self.identifier()?];
+        let joins = relationship
+            .article
+            .as_ref()
+            .is_some_and(|article| article.eq_ignore_ascii_case("the"));
+        self.more_arguments(&mut arguments, joins)?;
+
+        Ok(arguments)
     }
 
-    // If the clause is of the form `(clause)`
-    if collapsed[0].kind() == TokenType::LeftParen {
-        match find_close(tokens) {
-            Some(close) => {
-                return parse_clause(&tokens[1..close]);
-            }
-            None => {
-                return Err(ParseError::new(
-                    collapsed.last().unwrap().clone(),
-                    TokenType::RightParen,
-                ));
+    /// Parses any further right hand identifiers after the first, each following a preposition, or an 'and' if 'joins' is true.
+    fn more_arguments(
+        &mut self,
+        arguments: &mut Vec<ast::Identifier>,
+        joins: bool,
+    ) -> Result<(), ParseError> {
+        loop {
+            if let Some(preposition) = self.matches(TokenType::Prepostion) {
+                arguments.last_mut().unwrap().preposition = Some(preposition.lexeme().to_string());
+            } else if joins && self.continues_arguments() {
+                self.advance();
+            } else {
+                return Ok(());
             }
+            arguments.push(self.identifier()?);
         }
     }
 
-    // If the clause is of the form `article? identifier verb ‘not’? article? literal (preposition article? identifier)?`
-    if collapsed[0].is_identifier() {
-        //println!("found simple");
-        //dbg!(&tokens[i..end]);
-        let negated = collapsed[2].kind() == TokenType::Not;
-        let mut normalised: Vec<Token> = collapsed;
-        if negated {
-            normalised.remove(2);
+    /// Parses any identifiers joined to the last right hand identifier with 'and', as in `Ann likes Bob and Carol`.
+    /// The relationship holds for each of them in turn, in place of the last right hand identifier.
+    fn alternatives(&mut self) -> Result<Vec<ast::Identifier>, ParseError> {
+        let mut alternatives = Vec::new();
+        while self.continues_arguments() {
+            self.advance();
+            alternatives.push(self.identifier()?);
         }
 
-        let binary = type_between(&normalised, TokenType::Prepostion, TokenType::FullStop);
+        Ok(alternatives)
+    }
 
-        let mut left = ast::Identifier::try_from(&normalised[0])?;
-        if let Some(article) = &articles[0] {
-            left.article = Some(article.lexeme().to_string())
-        }
+    /// Parses an action, which is a verb such as 'likes' that names a relationship, with its right hand identifiers.
+    fn action(&mut self) -> Result<(ast::Identifier, Vec<ast::Identifier>), ParseError> {
+        use TokenType::*;
+
+        let mut relationship = ast::Identifier::try_from(self.expect(Literal)?)?;
+        relationship.kind = ast::IdenType::Verb;
+
+        // The first argument of an action, its object, needs no preposition
+        let arguments =
+            if [Article, Literal, Variable, Pronoun, Number].contains(&self.peek().kind()) {
+                let mut arguments = vec![self.identifier()?];
+                self.more_arguments(&mut arguments, false)?;
+                arguments
+            } else {
+                self.arguments(&mut relationship)?
+            };
+
+        Ok((relationship, arguments))
+    }
 
-        let mut relationship = ast::Identifier::try_from(&normalised[2])?;
-        if let Some(article) = &articles[1] {
-            relationship.article = Some(article.lexeme().to_string())
+    /// Parses a relationship after its verb, which may be a measure.
+    fn relationship(&mut self) -> Result<(ast::Identifier, Vec<ast::Identifier>), ParseError> {
+        if [TokenType::Number, TokenType::Variable, TokenType::Pronoun]
+            .contains(&self.peek().kind())
+        {
+            let right = self.identifier()?;
+            self.measure(right)
+        } else {
+            let mut relationship = self.literal()?;
+            let right = self.arguments(&mut relationship)?;
+            Ok((relationship, right))
         }
+    }
 
-        let right = if binary {
-            relationship.preposition = Some(normalised[3].lexeme().to_string());
-            let mut right_tmp = ast::Identifier::try_from(&normalised[4])?;
-            if let Some(article) = &articles[2] {
-                right_tmp.article = Some(article.lexeme().to_string())
-            }
-            Some(right_tmp)
-        } else if relationship.kind == ast::IdenType::Variable {
-            // special case for `X is not? Y`
-            let right_tmp = relationship;
-            relationship = ast::Identifier {
-                kind: ast::IdenType::Literal,
-                article: None,
-                lexeme: String::from("eq"),
-                preposition: None,
-            };
-            Some(right_tmp)
+    /// Parses everything in a statement after its left hand identifier and before its condition, which is a verb and a relationship or an action.
+    fn predicate(&mut self) -> Result<(ast::Identifier, Vec<ast::Identifier>), ParseError> {
+        if self.peek().kind() == TokenType::Literal {
+            self.action()
+        } else if self.matches(TokenType::Verb).is_some() {
+            self.relationship()
         } else {
-            None
+            self.unexpected(&[TokenType::Verb, TokenType::Literal])
+        }
+    }
+
+    /// Parses a quantified statement, such as `All hamsters are mammals.`, after its quantifier.
+    fn quantified(&mut self, quantifier: &Token) -> Result<Vec<ast::Stmt>, ParseError> {
+        let variable = ast::Identifier {
+            kind: ast::IdenType::Variable,
+            lexeme: ast::QUANTIFIED_VARIABLE.to_string(),
+            article: None,
+            preposition: None,
         };
 
-        let clause = ast::Clause::Simple {
-            negated,
-            left,
+        let mut noun = self.literal()?;
+        let noun_right = self.arguments(&mut noun)?;
+        let (mut relationship, right) = self.predicate()?;
+        let alternatives = self.alternatives_of(&right)?;
+        self.expect(TokenType::FullStop)?;
+
+        // After 'all', the quantified noun is plural, and so is the noun it is said to be, as in `All hamsters are mammals.`, but not a measure or a noun with an article
+        if quantifier.lexeme().eq_ignore_ascii_case("all") {
+            noun.kind = ast::IdenType::Plural;
+            if relationship.kind == ast::IdenType::Literal
+                && relationship.article.is_none()
+                && (right.is_empty() || relationship.preposition.is_some())
+            {
+                relationship.kind = ast::IdenType::Plural;
+            }
+        }
+
+        let stmt = ast::Stmt {
+            kind: ast::StmtType::Rule,
+            left: variable.clone(),
             relationship,
             right,
+            quantifier: Some(quantifier.lexeme().to_string()),
+            condition: Some(ast::Clause::Simple {
+                negated: false,
+                left: variable,
+                relationship: noun,
+                right: noun_right,
+            }),
+            span: 0..0,
         };
+        Ok(distribute(stmt, alternatives))
+    }
 
-        return Ok(clause);
+    /// Parses the identifiers joined to the last of 'right' with 'and', if there is one.
+    fn alternatives_of(
+        &mut self,
+        right: &[ast::Identifier],
+    ) -> Result<Vec<ast::Identifier>, ParseError> {
+        if right.is_empty() {
+            Ok(vec![])
+        } else {
+            self.alternatives()
+        }
     }
 
-    Err(ParseError {
-        token: collapsed[0].clone(),
-        expected: vec![
-            TokenType::LeftParen,
-            TokenType::Article,
-            TokenType::Literal,
-            TokenType::Variable,
-        ],
-    })
-}
+    /// Determines whether the token 'offset' tokens ahead is the unquoted literal 'word', ignoring case.
+    fn is_word(&self, offset: usize, word: &str) -> bool {
+        self.tokens.get(self.current + offset).is_some_and(|token| {
+            token.kind() == TokenType::Literal && token.lexeme().eq_ignore_ascii_case(word)
+        })
+    }
 
-/// Checks if a slice of tokens contains a token of type 'kind' before a terminator.
-fn tokens_contain(tokens: &[Token], kind: TokenType) -> Result<bool, ParseError> {
-    let mut i = 0;
-    while i < tokens.len() {
-        match is_terminator(tokens.get(i)) {
-            Some(result) => {
-                if result {
-                    return Ok(false);
-                }
-            }
-            None => {
-                return Err(ParseError::new(tokens[i - 1].clone(), TokenType::FullStop));
-            }
+    /// Consumes the next token, returning an error if it isn't the unquoted literal 'word'.
+    fn expect_word(&mut self, word: &str) -> Result<(), ParseError> {
+        if self.is_word(0, word) {
+            self.advance();
+            Ok(())
+        } else {
+            self.unexpected(&[TokenType::Literal])
         }
+    }
+
+    /// Builds a query about a hidden pronoun, which has the given name in its solutions.
+    fn hidden_query(
+        kind: ast::QueryType,
+        name: &str,
+        (relationship, right): (ast::Identifier, Vec<ast::Identifier>),
+    ) -> ast::Stmt {
+        ast::Stmt {
+            kind: ast::StmtType::Query(kind),
+            left: ast::Identifier {
+                kind: ast::IdenType::Pronoun,
+                lexeme: name.to_string(),
+                article: None,
+                preposition: None,
+            },
+            relationship,
+            right,
+            quantifier: None,
+            condition: None,
+            span: 0..0,
+        }
+    }
 
-        if tokens[i].kind() == kind {
-            return Ok(true);
+    /// Parses a query such as `Is there a mammal?` or `Is there anyone who likes Bob?`, after 'there'.
+    fn exists(&mut self) -> Result<ast::Stmt, ParseError> {
+        let anyone = ["anyone", "anything", "someone", "something"]
+            .iter()
+            .any(|word| self.is_word(0, word));
+        let pronoun = self
+            .tokens
+            .get(self.current + 1)
+            .is_some_and(|token| token.kind() == TokenType::Pronoun);
+        let predicate = if anyone && pronoun {
+            self.current += 2;
+            self.predicate()?
+        } else {
+            self.relationship()?
+        };
+        self.expect(TokenType::QuestionMark)?;
+
+        Ok(Self::hidden_query(
+            ast::QueryType::Exists,
+            "there",
+            predicate,
+        ))
+    }
+
+    /// Parses a query such as `How many children does Alice have?` or `How many mammals are there?`, after 'how many'.
+    fn count(&mut self) -> Result<ast::Stmt, ParseError> {
+        let mut noun = self.literal()?;
+        noun.kind = ast::IdenType::Plural;
+        let right = if self.matches(TokenType::Verb).is_some() {
+            self.expect_word("there")?;
+            vec![]
+        } else {
+            if self.is_word(0, "do") {
+                self.advance();
+            } else {
+                self.expect_word("does")?;
+            }
+            let owner = self.identifier()?;
+            self.expect_word("have")?;
+            vec![owner]
+        };
+        self.expect(TokenType::QuestionMark)?;
+
+        Ok(Self::hidden_query(
+            ast::QueryType::Count,
+            "how many",
+            (noun, right),
+        ))
+    }
+
+    /// Parses a query such as `List all mammals.`, after its quantifier.
+    fn list(&mut self) -> Result<ast::Stmt, ParseError> {
+        let mut noun = self.literal()?;
+        let right = self.arguments(&mut noun)?;
+        noun.kind = ast::IdenType::Plural;
+        if self.matches(TokenType::FullStop).is_none() {
+            self.expect(TokenType::QuestionMark)?;
         }
 
-        i += 1
+        let name = noun.lexeme.clone();
+        Ok(Self::hidden_query(
+            ast::QueryType::List,
+            &name,
+            (noun, right),
+        ))
     }
 
-    Ok(false)
-}
+    /// Parses a statement, including its terminator.
+    /// A statement whose last right hand identifier is joined to others with 'and' becomes one statement for each of them.
+    fn statement(&mut self) -> Result<Vec<ast::Stmt>, ParseError> {
+        use TokenType::*;
 
-/// Returns a copy of the next terminator along, with its location in 'tokens'.
-fn next_terminator(tokens: &[Token]) -> Result<(&Token, usize), ParseError> {
-    let mut i = 0;
-    loop {
-        match is_terminator(tokens.get(i)) {
-            Some(result) => {
-                if result {
-                    break;
-                }
+        if self.is_word(0, "how") && self.is_word(1, "many") {
+            self.current += 2;
+            return self.count().map(|stmt| vec![stmt]);
+        } else if self.is_word(0, "list")
+            && self
+                .tokens
+                .get(self.current + 1)
+                .is_some_and(|token| token.kind() == Quantifier)
+        {
+            self.current += 2;
+            return self.list().map(|stmt| vec![stmt]);
+        }
+
+        self.depth = 0;
+        self.hidden = 0;
+        let query = match self.peek().kind() {
+            Verb if self.is_word(1, "there") => {
+                self.current += 2;
+                return self.exists().map(|stmt| vec![stmt]);
             }
-            None => {
-                return Err(ParseError::new(tokens[i - 1].clone(), TokenType::FullStop));
+            Verb => {
+                self.advance();
+                true
+            }
+            Quantifier => {
+                let quantifier = self.advance();
+                return self.quantified(quantifier);
+            }
+            Article | Literal | Variable | Pronoun | Number => false,
+            _ => {
+                return self.unexpected(&[
+                    Article, Literal, Variable, Pronoun, Number, Verb, Quantifier,
+                ])
+            }
+        };
+
+        let left = self.identifier()?;
+        let (relationship, right) = if query {
+            match self.comparison() {
+                Some(comparison) => (ast::Identifier::from(comparison), vec![self.identifier()?]),
+                None => self.relationship()?,
             }
-        }
-        if i < tokens.len() {
-            i += 1
         } else {
-            break;
-        }
-    }
+            self.predicate()?
+        };
 
-    Ok((&tokens[i], i))
-}
+        // A query can only ask about one relationship, so it can't hold for each of a list
+        let and = self.peek().clone();
+        let alternatives = if query {
+            vec![]
+        } else {
+            self.alternatives_of(&right)?
+        };
 
-/// Finds the index of the next occurrence of 'kind' in 'tokens'.
-fn find_next(tokens: &[Token], kind: TokenType) -> usize {
-    let mut i = 0;
-    while i < tokens.len() {
-        if tokens[i].kind() == kind {
-            break;
+        let mut condition = None;
+        let kind = if query {
+            self.expect(QuestionMark)?;
+            ast::StmtType::Query(ast::QueryType::Solutions)
+        } else if self.matches(If).is_some() {
+            condition = Some(self.clause()?);
+            self.expect(FullStop)?;
+            ast::StmtType::Rule
+        } else if self.matches(FullStop).is_some() {
+            ast::StmtType::Fact
+        } else if self.matches(QuestionMark).is_some() {
+            ast::StmtType::Query(ast::QueryType::Solutions)
+        } else if right.is_empty() {
+            return self.unexpected(&[Prepostion, If, FullStop, QuestionMark]);
+        } else {
+            return self.unexpected(&[If, FullStop, QuestionMark]);
+        };
+        if matches!(kind, ast::StmtType::Query(_)) && !alternatives.is_empty() {
+            return Err(ParseError::new(and, QuestionMark));
         }
-        i += 1
+
+        let stmt = ast::Stmt {
+            kind,
+            left,
+            relationship,
+            right,
+            quantifier: None,
+            condition,
+            span: 0..0,
+        };
+        Ok(distribute(stmt, alternatives))
     }
 
-    i
-}
+    /// Parses a clause in the condition of a rule.
+    /// Operators all have the same precedence, and are right associative, so each change of operator nests the rest of the chain.
+    fn clause(&mut self) -> Result<ast::Clause, ParseError> {
+        let depth = self.depth;
+        let mut clauses = vec![self.operand()?];
+        let mut operators = Vec::new();
+
+        while let Some(operator) = self.matches(TokenType::Operator) {
+            trace!(operator = operator.lexeme(), "found operator");
+            let op_type = if operator.lexeme().to_lowercase() == "and" {
+                ast::OperatorType::And
+            } else {
+                ast::OperatorType::Or
+            };
 
-/// Parses a sequence of tokens into a statement, starting from 'i'.
-/// Returns the created statement along with the index it stopped at.
-fn parse_stmt(tokens: &[Token]) -> Result<(ast::Stmt, usize), ParseError> {
-    let (_, stmt_end) = next_terminator(tokens)?;
-
-    let mut binary = type_between(tokens, TokenType::Prepostion, TokenType::If);
-    let (collapsed, articles) = collapse_articles(tokens);
-    let mut left_index = 0;
-    let rel_index = 2;
-    let right_index = 4;
-    let mut kind = ast::StmtType::Fact;
-
-    let (next_term, _) = next_terminator(&collapsed)?;
-    if next_term.kind() == TokenType::QuestionMark {
-        match collapsed[0].kind() {
-            TokenType::Verb => {
-                binary = collapsed.len() == 6;
-                left_index = 1;
-                kind = ast::StmtType::Query;
-            }
-            TokenType::Literal | TokenType::Pronoun => {
-                binary = true;
-                kind = ast::StmtType::Query;
+            if operators.last().is_some_and(|last| *last != op_type) {
+                self.nest(operator)?;
             }
-            _ => {}
+            operators.push(op_type);
+            clauses.push(self.operand()?);
         }
+
+        self.depth = depth;
+        Ok(ast::Clause::chain(clauses, operators))
     }
 
-    let mut left = ast::Identifier::try_from(&collapsed[left_index])?;
-    if let Some(tmp) = &articles[0] {
-        left.article = Some(tmp.lexeme().to_string());
+    /// Parses one of the clauses joined by operators, which is either a simple clause or a clause in parentheses.
+    fn operand(&mut self) -> Result<ast::Clause, ParseError> {
+        let Some(paren) = self.matches(TokenType::LeftParen) else {
+            return self.simple_clause();
+        };
+
+        let depth = self.depth;
+        self.nest(paren)?;
+        let clause = self.clause()?;
+        self.expect(TokenType::RightParen)?;
+
+        self.depth = depth;
+        Ok(clause)
     }
 
-    let mut relationship = ast::Identifier::try_from(&collapsed[rel_index])?;
-    if let Some(tmp) = &articles[1] {
-        relationship.article = Some(tmp.lexeme().to_string());
+    /// Parses a comparison about a value described by a relationship, such as `the age of X is greater than 17`, after the relationship's literal.
+    /// The value is a hidden variable, so the clause becomes `V is the age of X and V is greater than 17`.
+    fn description(
+        &mut self,
+        mut relationship: ast::Identifier,
+    ) -> Result<ast::Clause, ParseError> {
+        use TokenType::*;
+
+        let right = self.arguments(&mut relationship)?;
+        self.expect(Verb)?;
+        let negated = self.matches(Not).is_some();
+        let comparison = match self.comparison() {
+            Some(comparison) => comparison,
+            None if [Number, Variable, Pronoun].contains(&self.peek().kind()) => {
+                ast::Comparison::Eq
+            }
+            None => return self.unexpected(&[Literal, Variable, Number]),
+        };
+        let value = self.identifier()?;
+
+        self.hidden += 1;
+        let hidden = ast::Identifier::hidden(self.hidden);
+        Ok(ast::Clause::Operator {
+            op_type: ast::OperatorType::And,
+            clauses: vec![
+                ast::Clause::Simple {
+                    negated: false,
+                    left: hidden.clone(),
+                    relationship,
+                    right,
+                },
+                ast::Clause::Simple {
+                    negated,
+                    left: hidden,
+                    relationship: ast::Identifier::from(comparison),
+                    right: vec![value],
+                },
+            ],
+        })
     }
 
-    let mut stmt = ast::Stmt {
-        kind,
-        left,
-        relationship,
-        right: None,
-        condition: None,
-    };
+    /// Parses a clause of the form `article? identifier verb 'not'? relationship`, where the relationship may be a comparison or a measure, or `article? identifier action`.
+    fn simple_clause(&mut self) -> Result<ast::Clause, ParseError> {
+        use TokenType::*;
+
+        if ![Article, Literal, Variable, Pronoun, Number].contains(&self.peek().kind()) {
+            return self.unexpected(&[LeftParen, Article, Literal, Variable, Number]);
+        }
 
-    if binary {
-        let preposition = collapsed[3].lexeme().to_string();
-        stmt.relationship.preposition = Some(preposition);
-        let mut right = ast::Identifier::try_from(&collapsed[right_index])?;
-        if let Some(article) = &articles[2] {
-            right.article = Some(article.lexeme().to_string())
+        trace!("found simple clause");
+        let left = self.identifier()?;
+        if left.kind == ast::IdenType::Literal && self.peek().kind() == Prepostion {
+            return self.description(left);
+        } else if self.peek().kind() == Literal {
+            let (relationship, right) = self.action()?;
+            let alternatives = self.alternatives_of(&right)?;
+            return Ok(distribute_clause(
+                ast::Clause::Simple {
+                    negated: false,
+                    left,
+                    relationship,
+                    right,
+                },
+                alternatives,
+            ));
+        } else if self.matches(Verb).is_none() {
+            return self.unexpected(&[Verb, Literal]);
         }
-        stmt.right = Some(right);
+        let negated = self.matches(Not).is_some();
+
+        let mut alternatives = vec![];
+        let (relationship, right) = match self.comparison() {
+            Some(comparison) => (ast::Identifier::from(comparison), vec![self.identifier()?]),
+            None => match self.peek().kind() {
+                Number | Variable | Pronoun => {
+                    let value = self.identifier()?;
+                    if self.peek().kind() == Literal {
+                        self.measure(value)?
+                    } else {
+                        // special case for `X is not? Y`
+                        (ast::Identifier::from(ast::Comparison::Eq), vec![value])
+                    }
+                }
+                Article | Literal => {
+                    let mut relationship = self.literal()?;
+                    let right = self.arguments(&mut relationship)?;
+                    alternatives = self.alternatives_of(&right)?;
+                    (relationship, right)
+                }
+                _ => return self.unexpected(&[Article, Literal, Variable, Number]),
+            },
+        };
+
+        Ok(distribute_clause(
+            ast::Clause::Simple {
+                negated,
+                left,
+                relationship,
+                right,
+            },
+            alternatives,
+        ))
     }
+}
 
-    // Rule
-    if tokens_contain(tokens, TokenType::If)? {
-        stmt.kind = ast::StmtType::Rule;
-        let clause_start = find_next(tokens, TokenType::If) + 1;
-        stmt.condition = Some(parse_clause(&tokens[clause_start..stmt_end])?);
+/// Makes a copy of a statement for each identifier that takes the place of its last right hand identifier.
+fn distribute(stmt: ast::Stmt, alternatives: Vec<ast::Identifier>) -> Vec<ast::Stmt> {
+    let copies: Vec<_> = alternatives
+        .into_iter()
+        .map(|alternative| {
+            let mut copy = stmt.clone();
+            *copy.right.last_mut().unwrap() = alternative;
+            copy
+        })
+        .collect();
+
+    [stmt].into_iter().chain(copies).collect()
+}
+
+/// Joins a copy of a simple clause with 'and' for each identifier that takes the place of its last right hand identifier.
+fn distribute_clause(clause: ast::Clause, alternatives: Vec<ast::Identifier>) -> ast::Clause {
+    if alternatives.is_empty() {
+        return clause;
     }
 
-    Ok((stmt, stmt_end))
+    let copies = alternatives.into_iter().map(|alternative| {
+        let mut copy = clause.clone();
+        if let ast::Clause::Simple { right, .. } = &mut copy {
+            *right.last_mut().unwrap() = alternative;
+        }
+        copy
+    });
+    ast::Clause::Operator {
+        op_type: ast::OperatorType::And,
+        clauses: [clause.clone()].into_iter().chain(copies).collect(),
+    }
+}
+
+/// Scans and parses a source string into an abstract syntax tree.
+/// Any error is given the location in the source that it refers to.
+pub(crate) fn parse_source(source: &str) -> Result<Vec<ast::Stmt>, ParseError> {
+    scanner::scan(source)
+        .and_then(|tokens| parse(&tokens))
+        .map_err(|err| err.locate(source))
+}
+
+/// Scans and parses a source string into an abstract syntax tree, recovering from errors.
+/// Returns every statement that could be parsed, and an error, with its location, for each statement that couldn't.
+pub(crate) fn parse_source_recovering(source: &str) -> (Vec<ast::Stmt>, Vec<ParseError>) {
+    let (trees, errors) = parse_recovering(&scanner::tokenise(source));
+    let errors = errors.into_iter().map(|err| err.locate(source)).collect();
+
+    (trees, errors)
 }
 
 /// Parses a sequence of tokens into an abstract syntax tree.
 pub fn parse(tokens: &[Token]) -> Result<Vec<ast::Stmt>, ParseError> {
+    let (trees, errors) = parse_recovering(tokens);
+    match errors.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(trees),
+    }
+}
+
+/// Parses a sequence of tokens into an abstract syntax tree.
+/// After an error, parsing resumes after the next full stop or question mark, so every error in the tokens is found.
+/// Returns every statement that could be parsed, and an error for each statement that couldn't.
+pub fn parse_recovering(tokens: &[Token]) -> (Vec<ast::Stmt>, Vec<ParseError>) {
     let mut trees = Vec::new();
+    let mut errors = Vec::new();
+    if tokens.is_empty() {
+        return (trees, errors);
+    }
 
-    let mut i = 0;
-    while i < tokens.len() {
-        use TokenType::*;
-        match tokens[i].kind() {
-            Eof => break,
-            Article | Literal | Variable | Pronoun | Verb => {
-                let (tree, end) = parse_stmt(&tokens[i..])?;
-                //dbg!(&tree);
-                trees.push(tree);
-                i += end + 1
+    let mut parser = Parser::new(tokens);
+    while !parser.is_at_end() {
+        let start = parser.current;
+        let _enter = debug_span!("statement", start = parser.peek().start()).entered();
+
+        // Any character the scanner couldn't recognise makes the whole statement invalid
+        let unrecognised = tokens[start..]
+            .iter()
+            .take_while(|token| !token.is_terminator())
+            .find(|token| token.kind() == TokenType::Error);
+
+        let result = match unrecognised {
+            Some(token) => Err(ParseError::from(token.clone())),
+            None => parser.statement(),
+        };
+
+        match result {
+            Ok(statements) => {
+                let end = &tokens[parser.current - 1];
+                let span = tokens[start].start()..end.start() + end.lexeme().len();
+                for tree in statements {
+                    debug!(kind = ?tree.kind(), "parsed statement");
+                    trees.push(tree.spanning(span.clone()));
+                }
             }
-            _ => {
-                return Err(ParseError {
-                    token: tokens[i].clone(),
-                    expected: Vec::from([Article, Literal, Variable, Pronoun, Verb]),
-                })
+            Err(err) => {
+                debug!(%err, "skipping statement");
+                errors.push(err);
+
+                // Make sure that the parser moves past the statement, even if the error was in its terminator
+                parser.current = start;
+                parser.synchronise();
             }
         }
     }
 
-    Ok(trees)
+    (trees, errors)
 }
 
 #[cfg(test)]
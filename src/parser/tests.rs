@@ -28,8 +28,10 @@ fn fact_unary() {
                 article: Some("a".to_string()),
                 preposition: None,
             },
-            right: None,
+            right: vec![],
+            quantifier: None,
             condition: None,
+            span: 0..22,
         }]),
     )
 }
@@ -52,13 +54,15 @@ fn fact_binary() {
                 article: Some("the".to_string()),
                 preposition: Some("of".to_string()),
             },
-            right: Some(Identifier {
+            right: vec![Identifier {
                 kind: Literal,
                 lexeme: "Jack".to_string(),
                 article: None,
                 preposition: None,
-            }),
+            }],
+            quantifier: None,
             condition: None,
+            span: 0..28,
         }]),
     )
 }
@@ -81,42 +85,46 @@ fn rule_unary() {
                 article: Some("a".to_string()),
                 preposition: None,
             },
-            right: None,
+            right: vec![],
+            quantifier: None,
             condition: Some(Clause::Operator {
                 op_type: And,
-                left: Box::new(Clause::Simple {
-                    negated: false,
-                    left: Identifier {
-                        kind: Variable,
-                        lexeme: "X".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                    relationship: Identifier {
-                        kind: Literal,
-                        lexeme: "animal".to_string(),
-                        article: Some("an".to_string()),
-                        preposition: None,
-                    },
-                    right: None,
-                }),
-                right: Box::new(Clause::Simple {
-                    negated: false,
-                    left: Identifier {
-                        kind: Variable,
-                        lexeme: "X".to_string(),
-                        article: None,
-                        preposition: None,
+                clauses: vec![
+                    Clause::Simple {
+                        negated: false,
+                        left: Identifier {
+                            kind: Variable,
+                            lexeme: "X".to_string(),
+                            article: None,
+                            preposition: None,
+                        },
+                        relationship: Identifier {
+                            kind: Literal,
+                            lexeme: "animal".to_string(),
+                            article: Some("an".to_string()),
+                            preposition: None,
+                        },
+                        right: vec![],
                     },
-                    relationship: Identifier {
-                        kind: Literal,
-                        lexeme: "warm-blooded".to_string(),
-                        article: None,
-                        preposition: None,
+                    Clause::Simple {
+                        negated: false,
+                        left: Identifier {
+                            kind: Variable,
+                            lexeme: "X".to_string(),
+                            article: None,
+                            preposition: None,
+                        },
+                        relationship: Identifier {
+                            kind: Literal,
+                            lexeme: "warm-blooded".to_string(),
+                            article: None,
+                            preposition: None,
+                        },
+                        right: vec![],
                     },
-                    right: None,
-                }),
+                ],
             }),
+            span: 0..54,
         }]),
     )
 }
@@ -139,52 +147,56 @@ fn rule_binary() {
                 article: Some("the".to_string()),
                 preposition: Some("of".to_string()),
             },
-            right: Some(Identifier {
+            right: vec![Identifier {
                 kind: Variable,
                 lexeme: "Y".to_string(),
                 article: None,
                 preposition: None,
-            }),
+            }],
+            quantifier: None,
             condition: Some(Clause::Operator {
                 op_type: And,
-                left: Box::new(Clause::Simple {
-                    negated: false,
-                    left: Identifier {
-                        kind: Variable,
-                        lexeme: "X".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                    relationship: Identifier {
-                        kind: Literal,
-                        lexeme: "sibling".to_string(),
-                        article: Some("the".to_string()),
-                        preposition: Some("of".to_string()),
-                    },
-                    right: Some(Identifier {
-                        kind: Variable,
-                        lexeme: "Y".to_string(),
-                        article: None,
-                        preposition: None,
-                    }),
-                }),
-                right: Box::new(Clause::Simple {
-                    negated: false,
-                    left: Identifier {
-                        kind: Variable,
-                        lexeme: "X".to_string(),
-                        article: None,
-                        preposition: None,
+                clauses: vec![
+                    Clause::Simple {
+                        negated: false,
+                        left: Identifier {
+                            kind: Variable,
+                            lexeme: "X".to_string(),
+                            article: None,
+                            preposition: None,
+                        },
+                        relationship: Identifier {
+                            kind: Literal,
+                            lexeme: "sibling".to_string(),
+                            article: Some("the".to_string()),
+                            preposition: Some("of".to_string()),
+                        },
+                        right: vec![Identifier {
+                            kind: Variable,
+                            lexeme: "Y".to_string(),
+                            article: None,
+                            preposition: None,
+                        }],
                     },
-                    relationship: Identifier {
-                        kind: Literal,
-                        lexeme: "male".to_string(),
-                        article: None,
-                        preposition: None,
+                    Clause::Simple {
+                        negated: false,
+                        left: Identifier {
+                            kind: Variable,
+                            lexeme: "X".to_string(),
+                            article: None,
+                            preposition: None,
+                        },
+                        relationship: Identifier {
+                            kind: Literal,
+                            lexeme: "male".to_string(),
+                            article: None,
+                            preposition: None,
+                        },
+                        right: vec![],
                     },
-                    right: None,
-                }),
+                ],
             }),
+            span: 0..61,
         }]),
     )
 }
@@ -207,85 +219,93 @@ fn rule_binary_parentheses() {
                 article: None,
                 preposition: Some("of".to_string()),
             },
-            right: Some(Identifier {
+            right: vec![Identifier {
                 kind: Variable,
                 lexeme: "C".to_string(),
                 article: None,
                 preposition: None,
-            }),
+            }],
+            quantifier: None,
             condition: Some(Clause::Operator {
                 op_type: Or,
-                left: Box::new(Clause::Operator {
-                    op_type: And,
-                    left: Box::new(Clause::Simple {
-                        negated: false,
-                        left: Identifier {
-                            kind: Variable,
-                            lexeme: "B".to_string(),
-                            article: None,
-                            preposition: None,
-                        },
-                        relationship: Identifier {
-                            kind: Literal,
-                            lexeme: "one".to_string(),
-                            article: None,
-                            preposition: None,
-                        },
-                        right: None,
-                    }),
-                    right: Box::new(Clause::Simple {
-                        negated: false,
-                        left: Identifier {
-                            kind: Variable,
-                            lexeme: "C".to_string(),
-                            article: None,
-                            preposition: None,
-                        },
-                        relationship: Identifier {
-                            kind: Literal,
-                            lexeme: "one".to_string(),
-                            article: None,
-                            preposition: None,
-                        },
-                        right: None,
-                    }),
-                }),
-                right: Box::new(Clause::Operator {
-                    op_type: And,
-                    left: Box::new(Clause::Simple {
-                        negated: false,
-                        left: Identifier {
-                            kind: Variable,
-                            lexeme: "B".to_string(),
-                            article: None,
-                            preposition: None,
-                        },
-                        relationship: Identifier {
-                            kind: Literal,
-                            lexeme: "two".to_string(),
-                            article: None,
-                            preposition: None,
-                        },
-                        right: None,
-                    }),
-                    right: Box::new(Clause::Simple {
-                        negated: false,
-                        left: Identifier {
-                            kind: Variable,
-                            lexeme: "C".to_string(),
-                            article: None,
-                            preposition: None,
-                        },
-                        relationship: Identifier {
-                            kind: Literal,
-                            lexeme: "two".to_string(),
-                            article: None,
-                            preposition: None,
-                        },
-                        right: None,
-                    }),
-                }),
+                clauses: vec![
+                    Clause::Operator {
+                        op_type: And,
+                        clauses: vec![
+                            Clause::Simple {
+                                negated: false,
+                                left: Identifier {
+                                    kind: Variable,
+                                    lexeme: "B".to_string(),
+                                    article: None,
+                                    preposition: None,
+                                },
+                                relationship: Identifier {
+                                    kind: Literal,
+                                    lexeme: "one".to_string(),
+                                    article: None,
+                                    preposition: None,
+                                },
+                                right: vec![],
+                            },
+                            Clause::Simple {
+                                negated: false,
+                                left: Identifier {
+                                    kind: Variable,
+                                    lexeme: "C".to_string(),
+                                    article: None,
+                                    preposition: None,
+                                },
+                                relationship: Identifier {
+                                    kind: Literal,
+                                    lexeme: "one".to_string(),
+                                    article: None,
+                                    preposition: None,
+                                },
+                                right: vec![],
+                            },
+                        ],
+                    },
+                    Clause::Operator {
+                        op_type: And,
+                        clauses: vec![
+                            Clause::Simple {
+                                negated: false,
+                                left: Identifier {
+                                    kind: Variable,
+                                    lexeme: "B".to_string(),
+                                    article: None,
+                                    preposition: None,
+                                },
+                                relationship: Identifier {
+                                    kind: Literal,
+                                    lexeme: "two".to_string(),
+                                    article: None,
+                                    preposition: None,
+                                },
+                                right: vec![],
+                            },
+                            Clause::Simple {
+                                negated: false,
+                                left: Identifier {
+                                    kind: Variable,
+                                    lexeme: "C".to_string(),
+                                    article: None,
+                                    preposition: None,
+                                },
+                                relationship: Identifier {
+                                    kind: Literal,
+                                    lexeme: "two".to_string(),
+                                    article: None,
+                                    preposition: None,
+                                },
+                                right: vec![],
+                            },
+                        ],
+                    },
+                ],
             }),
+            span: 0..70,
         }]),
     )
 }
@@ -308,38 +328,38 @@ fn rule_binary_negation() {
                 article: Some("the".to_string()),
                 preposition: Some("of".to_string()),
             },
-            right: Some(Identifier {
+            right: vec![Identifier {
                 kind: Variable,
                 lexeme: "Y".to_string(),
                 article: None,
                 preposition: None,
-            }),
+            }],
+            quantifier: None,
             condition: Some(Clause::Operator {
                 op_type: And,
-                left: Box::new(Clause::Simple {
-                    negated: false,
-                    left: Identifier {
-                        kind: Variable,
-                        lexeme: "Z".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                    relationship: Identifier {
-                        kind: Literal,
-                        lexeme: "parent".to_string(),
-                        article: Some("the".to_string()),
-                        preposition: Some("of".to_string()),
+                clauses: vec![
+                    Clause::Simple {
+                        negated: false,
+                        left: Identifier {
+                            kind: Variable,
+                            lexeme: "Z".to_string(),
+                            article: None,
+                            preposition: None,
+                        },
+                        relationship: Identifier {
+                            kind: Literal,
+                            lexeme: "parent".to_string(),
+                            article: Some("the".to_string()),
+                            preposition: Some("of".to_string()),
+                        },
+                        right: vec![Identifier {
+                            kind: Variable,
+                            lexeme: "X".to_string(),
+                            article: None,
+                            preposition: None,
+                        }],
                     },
-                    right: Some(Identifier {
-                        kind: Variable,
-                        lexeme: "X".to_string(),
-                        article: None,
-                        preposition: None,
-                    }),
-                }),
-                right: Box::new(Clause::Operator {
-                    op_type: And,
-                    left: Box::new(Clause::Simple {
+                    Clause::Simple {
                         negated: false,
                         left: Identifier {
                             kind: Variable,
@@ -353,14 +373,14 @@ fn rule_binary_negation() {
                             article: Some("the".to_string()),
                             preposition: Some("of".to_string()),
                         },
-                        right: Some(Identifier {
+                        right: vec![Identifier {
                             kind: Variable,
                             lexeme: "Y".to_string(),
                             article: None,
                             preposition: None,
-                        }),
-                    }),
-                    right: Box::new(Clause::Simple {
+                        }],
+                    },
+                    Clause::Simple {
                         negated: true,
                         left: Identifier {
                             kind: Variable,
@@ -374,15 +394,16 @@ fn rule_binary_negation() {
                             article: None,
                             preposition: None,
                         },
-                        right: Some(Identifier {
+                        right: vec![Identifier {
                             kind: Variable,
                             lexeme: "Y".to_string(),
                             article: None,
                             preposition: None,
-                        }),
-                    }),
-                }),
+                        }],
+                    },
+                ],
             }),
+            span: 0..86,
         }]),
     )
 }
@@ -392,7 +413,7 @@ fn query_literal() {
     test_trees_equal(
         "Is a hamster a mammal?",
         HashSet::from([Stmt {
-            kind: Query,
+            kind: Query(QueryType::Solutions),
             left: Identifier {
                 kind: Literal,
                 lexeme: "hamster".to_string(),
@@ -405,8 +426,10 @@ fn query_literal() {
                 article: Some("a".to_string()),
                 preposition: None,
             },
-            right: None,
+            right: vec![],
+            quantifier: None,
             condition: None,
+            span: 0..22,
         }]),
     )
 }
@@ -416,7 +439,7 @@ fn query_literal_literal() {
     test_trees_equal(
         "Is John the brother of Jack?",
         HashSet::from([Stmt {
-            kind: Query,
+            kind: Query(QueryType::Solutions),
             left: Identifier {
                 kind: Literal,
                 lexeme: "John".to_string(),
@@ -429,13 +452,15 @@ fn query_literal_literal() {
                 article: Some("the".to_string()),
                 preposition: Some("of".to_string()),
             },
-            right: Some(Identifier {
+            right: vec![Identifier {
                 kind: Literal,
                 lexeme: "Jack".to_string(),
                 article: None,
                 preposition: None,
-            }),
+            }],
+            quantifier: None,
             condition: None,
+            span: 0..28,
         }]),
     )
 }
@@ -445,7 +470,7 @@ fn query_literal_pronoun() {
     test_trees_equal(
         "John is the brother of who?",
         HashSet::from([Stmt {
-            kind: Query,
+            kind: Query(QueryType::Solutions),
             left: Identifier {
                 kind: Literal,
                 lexeme: "John".to_string(),
@@ -458,13 +483,15 @@ fn query_literal_pronoun() {
                 article: Some("the".to_string()),
                 preposition: Some("of".to_string()),
             },
-            right: Some(Identifier {
+            right: vec![Identifier {
                 kind: Pronoun,
                 lexeme: "who".to_string(),
                 article: None,
                 preposition: None,
-            }),
+            }],
+            quantifier: None,
             condition: None,
+            span: 0..27,
         }]),
     )
 }
@@ -474,7 +501,7 @@ fn query_pronoun_literal() {
     test_trees_equal(
         "Who is the brother of Jane?",
         HashSet::from([Stmt {
-            kind: Query,
+            kind: Query(QueryType::Solutions),
             left: Identifier {
                 kind: Pronoun,
                 lexeme: "Who".to_string(),
@@ -487,13 +514,15 @@ fn query_pronoun_literal() {
                 article: Some("the".to_string()),
                 preposition: Some("of".to_string()),
             },
-            right: Some(Identifier {
+            right: vec![Identifier {
                 kind: Literal,
                 lexeme: "Jane".to_string(),
                 article: None,
                 preposition: None,
-            }),
+            }],
+            quantifier: None,
             condition: None,
+            span: 0..27,
         }]),
     )
 }
@@ -503,7 +532,7 @@ fn query_pronoun_pronoun() {
     test_trees_equal(
         "Who is the sister of who?",
         HashSet::from([Stmt {
-            kind: Query,
+            kind: Query(QueryType::Solutions),
             left: Identifier {
                 kind: Pronoun,
                 lexeme: "Who".to_string(),
@@ -516,13 +545,15 @@ fn query_pronoun_pronoun() {
                 article: Some("the".to_string()),
                 preposition: Some("of".to_string()),
             },
-            right: Some(Identifier {
+            right: vec![Identifier {
                 kind: Pronoun,
                 lexeme: "who".to_string(),
                 article: None,
                 preposition: None,
-            }),
+            }],
+            quantifier: None,
             condition: None,
+            span: 0..25,
         }]),
     )
 }
@@ -548,8 +579,10 @@ fn program_1() {
                 ),
                 preposition: None,
             },
-            right: None,
+            right: vec![],
+            quantifier: None,
             condition: None,
+            span: 0..23,
         },
         Stmt {
             kind: Fact,
@@ -567,8 +600,10 @@ fn program_1() {
                 article: None,
                 preposition: None,
             },
-            right: None,
+            right: vec![],
+            quantifier: None,
             condition: None,
+            span: 24..50,
         },
         Stmt {
             kind: Rule,
@@ -586,11 +621,10 @@ fn program_1() {
                 ),
                 preposition: None,
             },
-            right: None,
+            right: vec![],
+            quantifier: None,
             condition: Some(
-                Clause::Operator {
-                    op_type: And,
-                    left: Box::new(Clause::Simple {
+                Clause::Operator { op_type: And, clauses: vec![Clause::Simple {
                         negated: false,
                         left: Identifier {
                             kind: Variable,
@@ -606,9 +640,8 @@ fn program_1() {
                             ),
                             preposition: None,
                         },
-                        right: None,
-                    }),
-                    right: Box::new(Clause::Simple {
+                        right: vec![],
+                    }, Clause::Simple {
                         negated: false,
                         left: Identifier {
                             kind: Variable,
@@ -622,10 +655,10 @@ fn program_1() {
                             article: None,
                             preposition: None,
                         },
-                        right: None,
-                    }),
-                },
+                        right: vec![],
+                    }] },
             ),
+            span: 51..105,
         },
     ]))
 }
@@ -649,13 +682,15 @@ fn program_2() {
                     article: Some("the".to_string()),
                     preposition: Some("of".to_string()),
                 },
-                right: Some(Identifier {
+                right: vec![Identifier {
                     kind: Literal,
                     lexeme: "Jack".to_string(),
                     article: None,
                     preposition: None,
-                }),
+                }],
+                quantifier: None,
                 condition: None,
+                span: 0..27,
             },
             Stmt {
                 kind: Fact,
@@ -671,13 +706,15 @@ fn program_2() {
                     article: Some("the".to_string()),
                     preposition: Some("of".to_string()),
                 },
-                right: Some(Identifier {
+                right: vec![Identifier {
                     kind: Literal,
                     lexeme: "Jane".to_string(),
                     article: None,
                     preposition: None,
-                }),
+                }],
+                quantifier: None,
                 condition: None,
+                span: 28..55,
             },
             Stmt {
                 kind: Rule,
@@ -693,15 +730,14 @@ fn program_2() {
                     article: Some("the".to_string()),
                     preposition: Some("of".to_string()),
                 },
-                right: Some(Identifier {
+                right: vec![Identifier {
                     kind: Variable,
                     lexeme: "Y".to_string(),
                     article: None,
                     preposition: None,
-                }),
-                condition: Some(Clause::Operator {
-                    op_type: And,
-                    left: Box::new(Clause::Simple {
+                }],
+                quantifier: None,
+                condition: Some(Clause::Operator { op_type: And, clauses: vec![Clause::Simple {
                         negated: false,
                         left: Identifier {
                             kind: Variable,
@@ -715,16 +751,13 @@ fn program_2() {
                             article: Some("the".to_string()),
                             preposition: Some("of".to_string()),
                         },
-                        right: Some(Identifier {
+                        right: vec![Identifier {
                             kind: Variable,
                             lexeme: "X".to_string(),
                             article: None,
                             preposition: None,
-                        }),
-                    }),
-                    right: Box::new(Clause::Operator {
-                        op_type: And,
-                        left: Box::new(Clause::Simple {
+                        }],
+                    }, Clause::Simple {
                             negated: false,
                             left: Identifier {
                                 kind: Variable,
@@ -738,14 +771,13 @@ fn program_2() {
                                 article: Some("the".to_string()),
                                 preposition: Some("of".to_string()),
                             },
-                            right: Some(Identifier {
+                            right: vec![Identifier {
                                 kind: Variable,
                                 lexeme: "Y".to_string(),
                                 article: None,
                                 preposition: None,
-                            }),
-                        }),
-                        right: Box::new(Clause::Simple {
+                            }],
+                        }, Clause::Simple {
                             negated: true,
                             left: Identifier {
                                 kind: Variable,
@@ -759,16 +791,728 @@ fn program_2() {
                                 article: None,
                                 preposition: None,
                             },
-                            right: Some(Identifier {
+                            right: vec![Identifier {
                                 kind: Variable,
                                 lexeme: "Y".to_string(),
                                 article: None,
                                 preposition: None,
-                            }),
-                        }),
-                    }),
-                }),
+                            }],
+                        }] }),
+                span: 56..142,
             },
         ]),
     );
 }
+
+#[test]
+fn proper_names() {
+    let trees = parse_source("New York is the capital of New  York State.").unwrap();
+
+    assert_eq!(trees[0].left().lexeme(), "New York");
+    assert_eq!(trees[0].left().kind(), Literal);
+    assert_eq!(
+        trees[0]
+            .right()
+            .iter()
+            .map(Identifier::lexeme)
+            .collect::<Vec<_>>(),
+        ["New York State"]
+    );
+    assert_eq!(
+        trees[0].to_string(),
+        "New York is the capital of New York State."
+    );
+
+    let trees = parse_source(r#""New  York" is a city."#).unwrap();
+    assert_eq!(trees[0].left().lexeme(), "New  York");
+    assert_eq!(trees[0].to_string(), r#""New  York" is a city."#);
+}
+
+#[test]
+fn quoted_names() {
+    let trees =
+        parse_source(r#""New York" is a city. "NASA" is the agency of "the \"US\" \\ them"."#)
+            .unwrap();
+
+    assert_eq!(trees[0].left().lexeme(), "New York");
+    assert_eq!(trees[1].left().lexeme(), "NASA");
+    assert_eq!(trees[1].left().kind(), Literal);
+    assert_eq!(
+        trees[1]
+            .right()
+            .iter()
+            .map(Identifier::lexeme)
+            .collect::<Vec<_>>(),
+        [r#"the "US" \ them"#]
+    );
+    assert_eq!(
+        trees.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec![
+            "New York is a city.",
+            r#""NASA" is the agency of "the \"US\" \\ them"."#,
+        ]
+    );
+}
+
+#[test]
+fn error_unterminated_quote() {
+    let err = parse_source("\"New York is a city.\nJohn is a man.").unwrap_err();
+
+    assert_eq!(err.span(), 0..20);
+    assert_eq!(
+        err.to_string(),
+        "line 1, column 1: unterminated quoted name"
+    );
+}
+
+#[test]
+fn comparisons() {
+    let trees = parse_source(
+        "X is an adult if Y is the age of X and Y is at least 18 and Y is not greater than 120.",
+    )
+    .unwrap();
+
+    assert_eq!(
+        trees[0].condition(),
+        &Some(Clause::Operator {
+            op_type: And,
+            clauses: vec![
+                Clause::Simple {
+                    negated: false,
+                    left: Identifier {
+                        kind: Variable,
+                        lexeme: String::from("Y"),
+                        article: None,
+                        preposition: None,
+                    },
+                    relationship: Identifier {
+                        kind: Literal,
+                        lexeme: String::from("age"),
+                        article: Some(String::from("the")),
+                        preposition: Some(String::from("of")),
+                    },
+                    right: vec![Identifier {
+                        kind: Variable,
+                        lexeme: String::from("X"),
+                        article: None,
+                        preposition: None,
+                    }],
+                },
+                Clause::Simple {
+                    negated: false,
+                    left: Identifier {
+                        kind: Variable,
+                        lexeme: String::from("Y"),
+                        article: None,
+                        preposition: None,
+                    },
+                    relationship: Identifier::from(Comparison::Ge),
+                    right: vec![Identifier {
+                        kind: Number,
+                        lexeme: String::from("18"),
+                        article: None,
+                        preposition: None,
+                    }],
+                },
+                Clause::Simple {
+                    negated: true,
+                    left: Identifier {
+                        kind: Variable,
+                        lexeme: String::from("Y"),
+                        article: None,
+                        preposition: None,
+                    },
+                    relationship: Identifier::from(Comparison::Gt),
+                    right: vec![Identifier {
+                        kind: Number,
+                        lexeme: String::from("120"),
+                        article: None,
+                        preposition: None,
+                    }],
+                }
+            ]
+        })
+    );
+    assert_eq!(
+        trees[0].to_string(),
+        "X is an adult if Y is the age of X and Y is at least 18 and Y is not greater than 120."
+    );
+}
+
+#[test]
+fn described_comparisons() {
+    let source = "X is an adult if the age of X is greater than 17. Is 34 greater than 17?";
+    let trees = parse_source(source).unwrap();
+
+    let value = Identifier {
+        kind: Variable,
+        lexeme: String::from("_1"),
+        article: None,
+        preposition: None,
+    };
+    let number = |lexeme: &str| Identifier {
+        kind: Number,
+        lexeme: String::from(lexeme),
+        article: None,
+        preposition: None,
+    };
+    assert_eq!(
+        trees[0].condition(),
+        &Some(Clause::Operator {
+            op_type: And,
+            clauses: vec![
+                Clause::Simple {
+                    negated: false,
+                    left: value.clone(),
+                    relationship: Identifier {
+                        kind: Literal,
+                        lexeme: String::from("age"),
+                        article: Some(String::from("the")),
+                        preposition: Some(String::from("of")),
+                    },
+                    right: vec![Identifier {
+                        kind: Variable,
+                        lexeme: String::from("X"),
+                        article: None,
+                        preposition: None,
+                    }],
+                },
+                Clause::Simple {
+                    negated: false,
+                    left: value,
+                    relationship: Identifier::from(Comparison::Gt),
+                    right: vec![number("17")],
+                },
+            ]
+        })
+    );
+    assert_eq!(trees[1].left(), &number("34"));
+    assert_eq!(trees[1].relationship(), &Identifier::from(Comparison::Gt));
+    assert_eq!(trees[1].right(), &[number("17")]);
+
+    let displayed: Vec<_> = trees.iter().map(ToString::to_string).collect();
+    assert_eq!(displayed.join(" "), source);
+
+    // Each described value is a different hidden variable
+    let trees = parse_source(
+        "X is the elder of Y if (the age of X is Z and the age of Y is not at least Z) or X is old.",
+    )
+    .unwrap();
+    assert_eq!(
+        trees[0].to_string(),
+        "X is the elder of Y if (the age of X is Z and the age of Y is not at least Z) or X is old."
+    );
+}
+
+#[test]
+fn measures() {
+    let trees = parse_source(
+        "Alice is 034.50 years old. X is an adult if X is N years old and N is less than 18.",
+    )
+    .unwrap();
+
+    assert_eq!(trees[0].relationship().lexeme(), "years old");
+    assert_eq!(
+        trees[0]
+            .right()
+            .iter()
+            .map(Identifier::lexeme)
+            .collect::<Vec<_>>(),
+        ["34.5"]
+    );
+    assert_eq!(
+        trees.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec![
+            "Alice is 34.5 years old.",
+            "X is an adult if X is N years old and N is less than 18.",
+        ]
+    );
+}
+
+#[test]
+fn listed_objects() {
+    let trees =
+        parse_source("Ann likes Bob and Carol. X is happy if X likes Bob and Carol.").unwrap();
+
+    // Each object gets its own statement or clause, so 'likes' stays a relationship of two
+    let displayed: Vec<_> = trees.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        displayed,
+        [
+            "Ann likes Bob.",
+            "Ann likes Carol.",
+            "X is happy if X likes Bob and X likes Carol."
+        ]
+    );
+    assert!(trees[..2].iter().all(|tree| tree.right().len() == 1));
+    assert_eq!(trees[0].span(), trees[1].span());
+
+    assert_eq!(
+        parse_error("Who likes Bob and Carol?"),
+        "expected question mark, found operator"
+    );
+}
+
+#[test]
+fn listed_arguments() {
+    let trees =
+        parse_source("Alice is a friend of Bob and Carol. X is the sum of Y and Z.").unwrap();
+
+    // A list after 'a friend of' says two things, but after 'the sum of' it names the arguments of one relationship
+    let displayed: Vec<_> = trees.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        displayed,
+        [
+            "Alice is a friend of Bob.",
+            "Alice is a friend of Carol.",
+            "X is the sum of Y and Z."
+        ]
+    );
+    assert_eq!(trees[1].right().len(), 1);
+    assert_eq!(trees[2].right().len(), 2);
+}
+
+#[test]
+fn n_ary() {
+    let trees = parse_source(
+        "Alice is the sender of a letter to Bob. X is the sum of Y and Z if X is the total of Y and Z and Y is a number.",
+    )
+    .unwrap();
+
+    assert_eq!(
+        trees[0].relationship().preposition(),
+        &Some(String::from("of"))
+    );
+    assert_eq!(
+        trees[0].right(),
+        [
+            Identifier {
+                kind: Literal,
+                lexeme: String::from("letter"),
+                article: Some(String::from("a")),
+                preposition: Some(String::from("to")),
+            },
+            Identifier {
+                kind: Literal,
+                lexeme: String::from("Bob"),
+                article: None,
+                preposition: None,
+            },
+        ]
+    );
+    assert_eq!(
+        trees[1]
+            .right()
+            .iter()
+            .map(Identifier::lexeme)
+            .collect::<Vec<_>>(),
+        ["Y", "Z"]
+    );
+    // The 'and' before 'Y is a number' starts another clause
+    assert!(matches!(
+        trees[1].condition(),
+        Some(Clause::Operator { clauses, .. })
+            if matches!(&clauses[0], Clause::Simple { right, .. } if right.len() == 2)
+    ));
+    assert_eq!(
+        trees.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec![
+            "Alice is the sender of a letter to Bob.",
+            "X is the sum of Y and Z if X is the total of Y and Z and Y is a number.",
+        ]
+    );
+}
+
+#[test]
+fn actions() {
+    let trees = parse_source(
+        "Alice likes Bob. Alice gave the book to Bob. Bob sleeps. Who likes Bob? X admires Y if X likes Y and Y is famous.",
+    )
+    .unwrap();
+
+    assert_eq!(
+        trees[0].relationship(),
+        &Identifier {
+            kind: Verb,
+            lexeme: String::from("likes"),
+            article: None,
+            preposition: None,
+        }
+    );
+    assert_eq!(
+        trees[1]
+            .right()
+            .iter()
+            .map(Identifier::lexeme)
+            .collect::<Vec<_>>(),
+        ["book", "Bob"]
+    );
+    assert!(trees[2].right().is_empty());
+    assert_eq!(trees[3].kind(), Query(QueryType::Solutions));
+    assert!(matches!(
+        trees[4].condition(),
+        Some(Clause::Operator { clauses, .. })
+            if matches!(&clauses[0], Clause::Simple { relationship, right, .. }
+                if relationship.kind() == Verb && right.len() == 1)
+            && matches!(&clauses[1], Clause::Simple { .. })
+    ));
+    assert_eq!(
+        trees.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec![
+            "Alice likes Bob.",
+            "Alice gave the book to Bob.",
+            "Bob sleeps.",
+            "Who likes Bob?",
+            "X admires Y if X likes Y and Y is famous.",
+        ]
+    );
+}
+
+#[test]
+fn error_action() {
+    let err = parse_source("Alice likes the cat dog.").unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "line 1, column 21: expected 'if', full stop or question mark, found literal"
+    );
+}
+
+#[test]
+fn quantified() {
+    let trees = parse_source(
+        "All hamsters are mammals. Every parent of X is an ancestor of X. Each cat eats fish.",
+    )
+    .unwrap();
+
+    let variable = Identifier {
+        kind: Variable,
+        lexeme: String::from(QUANTIFIED_VARIABLE),
+        article: None,
+        preposition: None,
+    };
+    assert_eq!(
+        trees[1],
+        Stmt {
+            kind: Rule,
+            left: variable.clone(),
+            relationship: Identifier {
+                kind: Literal,
+                lexeme: String::from("ancestor"),
+                article: Some(String::from("an")),
+                preposition: Some(String::from("of")),
+            },
+            right: vec![Identifier {
+                kind: Variable,
+                lexeme: String::from("X"),
+                article: None,
+                preposition: None,
+            }],
+            quantifier: Some(String::from("Every")),
+            condition: Some(Clause::Simple {
+                negated: false,
+                left: variable,
+                relationship: Identifier {
+                    kind: Literal,
+                    lexeme: String::from("parent"),
+                    article: None,
+                    preposition: Some(String::from("of")),
+                },
+                right: vec![Identifier {
+                    kind: Variable,
+                    lexeme: String::from("X"),
+                    article: None,
+                    preposition: None,
+                }],
+            }),
+            span: 26..64,
+        }
+    );
+    assert_eq!(
+        trees.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec![
+            "All hamsters are mammals.",
+            "Every parent of X is an ancestor of X.",
+            "Each cat eats fish.",
+        ]
+    );
+}
+
+#[test]
+fn singular_names() {
+    assert_eq!(singular("mammals"), "mammal");
+    assert_eq!(singular("cities"), "city");
+    assert_eq!(singular("boxes"), "box");
+    assert_eq!(singular("glass"), "glass");
+    assert_eq!(singular("bus"), "bus");
+    assert_eq!(singular("new cars"), "new car");
+    assert_eq!(singular("mammal"), "mammal");
+    assert_eq!(singular("children"), "child");
+}
+
+#[test]
+fn query_types() {
+    let trees = parse_source(
+        "Is there a mammal? Is there anyone who likes Bob? How many children does Alice have? How many mammals are there? List all children of Alice.",
+    )
+    .unwrap();
+
+    assert_eq!(
+        trees.iter().map(Stmt::kind).collect::<Vec<_>>(),
+        vec![
+            Query(QueryType::Exists),
+            Query(QueryType::Exists),
+            Query(QueryType::Count),
+            Query(QueryType::Count),
+            Query(QueryType::List),
+        ]
+    );
+    assert_eq!(
+        trees[2].left(),
+        &Identifier {
+            kind: Pronoun,
+            lexeme: String::from("how many"),
+            article: None,
+            preposition: None,
+        }
+    );
+    assert_eq!(trees[2].relationship().lexeme(), "children");
+    assert_eq!(trees[2].relationship().name(), "child");
+    assert_eq!(
+        trees[2]
+            .right()
+            .iter()
+            .map(Identifier::lexeme)
+            .collect::<Vec<_>>(),
+        ["Alice"]
+    );
+    assert_eq!(trees[4].left().lexeme(), "children");
+    assert_eq!(
+        trees.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec![
+            "Is there a mammal?",
+            "Is there anyone who likes Bob?",
+            "How many children does Alice have?",
+            "How many mammals are there?",
+            "List all children of Alice.",
+        ]
+    );
+}
+
+#[test]
+fn error_count() {
+    let err = parse_source("How many children does Alice?").unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "line 1, column 29: expected literal, found question mark"
+    );
+}
+
+#[test]
+fn error_location() {
+    let err = parse_source("John is the brother of Jack.\nJack is %.").unwrap_err();
+
+    assert_eq!(err.span(), 37..38);
+    assert_eq!(err.location(), Some(Location { line: 2, column: 9 }));
+    assert_eq!(err.to_string(), "line 2, column 9: unexpected character: %");
+}
+
+#[test]
+fn error_expected_one_of() {
+    let err = parse_source("John is a man.\n  (Jack is a man.").unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "line 2, column 3: expected article, literal, variable, pronoun, number, verb or quantifier, found left parenthesis"
+    );
+}
+
+#[test]
+fn error_render() {
+    let err = parse_source("John is a man.\n  (Jack is a man.").unwrap_err();
+
+    assert_eq!(
+        err.render(),
+        "error: line 2, column 3: expected article, literal, variable, pronoun, number, verb or quantifier, found left parenthesis\n  |\n2 |   (Jack is a man.\n  |   ^"
+    );
+}
+
+#[test]
+fn error_render_without_source() {
+    let err = parse(&scan("(Jack is a man.").unwrap()).unwrap_err();
+
+    assert_eq!(err.location(), None);
+    assert_eq!(err.render(), err.to_string());
+}
+
+#[test]
+fn recover_errors() {
+    let (trees, errors) = parse_source_recovering(
+        "John is the brother of Jack.\n(Jack is a man.\nJim is %.\nJim is a man.",
+    );
+
+    assert_eq!(trees.len(), 2);
+    assert_eq!(
+        errors.iter().map(ParseError::location).collect::<Vec<_>>(),
+        vec![
+            Some(Location { line: 2, column: 1 }),
+            Some(Location { line: 3, column: 8 }),
+        ]
+    );
+}
+
+#[test]
+fn recover_first_error() {
+    let tokens = scan("(Jack is a man. Jim is a man.").unwrap();
+
+    assert_eq!(
+        parse(&tokens).unwrap_err().to_string(),
+        parse_recovering(&tokens).1[0].to_string()
+    );
+}
+
+fn parse_error(source: &str) -> String {
+    parse(&scan(source).unwrap()).unwrap_err().to_string()
+}
+
+#[test]
+fn error_missing_relationship() {
+    assert_eq!(parse_error("John is."), "expected literal, found full stop");
+    assert_eq!(
+        parse_error("X is the of Y."),
+        "expected literal, found preposition"
+    );
+    assert_eq!(
+        parse_error("Is John?"),
+        "expected literal, found question mark"
+    );
+}
+
+#[test]
+fn error_reserved() {
+    assert_eq!(
+        parse_error("X is gt of Y."),
+        "'gt' is reserved for a builtin comparison"
+    );
+    assert_eq!(
+        parse_error("X is the Eq of Y."),
+        "'Eq' is reserved for a builtin comparison"
+    );
+    assert_eq!(
+        parse_error("All dogs are les."),
+        "'les' is reserved for a builtin comparison"
+    );
+    // Proper names aren't plurals
+    assert!(parse(&scan("Les is a person.").unwrap()).is_ok());
+}
+
+#[test]
+fn error_missing_terminator() {
+    assert_eq!(
+        parse_error("John is a man"),
+        "expected preposition, 'if', full stop or question mark, found end of file"
+    );
+    assert_eq!(
+        parse_error("X is a man if (X is a person."),
+        "expected right parenthesis, found full stop"
+    );
+    assert_eq!(
+        parse_error("Is John a man."),
+        "expected question mark, found full stop"
+    );
+}
+
+#[test]
+fn error_missing_condition() {
+    assert_eq!(
+        parse_error("X is a man if."),
+        "expected left parenthesis, article, literal, variable or number, found full stop"
+    );
+    assert_eq!(
+        parse_error("X is a man if X is a person and."),
+        "expected left parenthesis, article, literal, variable or number, found full stop"
+    );
+}
+
+#[test]
+fn long_chains() {
+    let source = format!(
+        "X is bad if X is a thing{}.",
+        " and X is bad".repeat(10_000)
+    );
+    let trees = parse_source(&source).unwrap();
+
+    assert!(matches!(
+        trees[0].condition(),
+        Some(Clause::Operator { op_type: And, clauses }) if clauses.len() == 10_001
+    ));
+    assert_eq!(trees[0].to_string(), source);
+}
+
+#[test]
+fn error_nested_too_deeply() {
+    assert_eq!(
+        parse_error(&format!(
+            "X is bad if {}X is a thing{}.",
+            "(".repeat(10_000),
+            ")".repeat(10_000)
+        )),
+        "clauses are nested more than 100 deep, found left parenthesis"
+    );
+    // Operators are right associative, so each change of operator nests the rest of the chain
+    assert_eq!(
+        parse_error(&format!(
+            "X is bad if X is a thing{}.",
+            " and X is bad or X is good".repeat(10_000)
+        )),
+        "clauses are nested more than 100 deep, found operator"
+    );
+
+    let source = format!(
+        "X is bad if {}X is a thing{}.",
+        "(".repeat(100),
+        ")".repeat(100)
+    );
+    assert!(parse(&scan(&source).unwrap()).is_ok());
+}
+
+#[test]
+fn parse_never_panics() {
+    let programs = [
+        "A hamster is an animal. A hamster is warm-blooded. X is a mammal if X is an animal and X is warm-blooded.",
+        "B is thing of C if (B is one and C is one) or (B is two and C is two).",
+        "X is the sibling of Y if Z is the parent of X and Z is the parent of Y and X is not Y.",
+        "Is John the brother of Jack? Who is the sister of who? John is the brother of who?",
+    ];
+
+    // Every prefix and suffix of a valid program, cut at any token boundary, must parse or return an error
+    for program in programs {
+        let tokens = scan(program).unwrap();
+        let (eof, tokens) = tokens.split_last().unwrap();
+        for i in 0..=tokens.len() {
+            let mut prefix = tokens[..i].to_vec();
+            prefix.push(eof.clone());
+            let _ = parse_recovering(&prefix);
+
+            let mut suffix = tokens[i..].to_vec();
+            suffix.push(eof.clone());
+            let _ = parse_recovering(&suffix);
+        }
+    }
+
+    let _ = parse_recovering(&[]);
+}
+
+#[test]
+fn statement_spans() {
+    let source = "Alice likes Bob.\nIs there anyone who likes Bob?  All cats are mammals.";
+    let spans: Vec<_> = parse_source(source)
+        .unwrap()
+        .iter()
+        .map(Stmt::span)
+        .collect();
+
+    assert_eq!(spans, vec![0..16, 17..47, 49..70]);
+    assert_eq!(&source[spans[1].clone()], "Is there anyone who likes Bob?");
+}
@@ -1,4 +1,6 @@
-use std::{fmt, hash::Hash};
+use std::{fmt, hash::Hash, iter::Peekable, str::CharIndices};
+
+use tracing::{debug, debug_span};
 
 use super::parser::ParseError;
 
@@ -19,10 +21,12 @@ pub enum TokenType {
     If,
     Pronoun,
     Not,
+    Quantifier,
 
     // Identifiers
     Literal,
     Variable,
+    Number,
 
     Eof,
 
@@ -43,8 +47,10 @@ impl fmt::Display for TokenType {
             If => "'if'",
             Pronoun => "pronoun",
             Not => "'not'",
+            Quantifier => "quantifier",
             Literal => "literal",
             Variable => "variable",
+            Number => "number",
             Eof => "end of file",
             Error => "error",
         };
@@ -104,7 +110,7 @@ impl Token {
 
     /// Determines whether the token is a user-defined identifier.
     pub fn is_identifier(&self) -> bool {
-        [Literal, Variable].contains(&self.kind)
+        [Literal, Variable, Number].contains(&self.kind)
     }
 
     pub fn kind(&self) -> TokenType {
@@ -115,6 +121,7 @@ impl Token {
         &self.lexeme
     }
 
+    /// The byte offset of the start of the token in the source it was scanned from.
     pub fn start(&self) -> usize {
         self.start
     }
@@ -125,85 +132,156 @@ fn valid_iden(c: char) -> bool {
     c.is_alphabetic() || c == '-' || c == '_'
 }
 
-/// Extracts a single word from the given source string, starting at 'start'.
-fn get_word(source: &str, start: usize) -> String {
-    let mut end = start;
-    while end < source.len() && valid_iden(source.chars().nth(end).unwrap()) {
-        end += 1;
+/// Determines the type of a single word.
+fn word_kind(word: &str) -> TokenType {
+    match word.to_lowercase().as_str() {
+        "a" | "an" | "the" => Article,
+        "and" | "or" => Operator,
+        "of" | "to" | "from" | "with" | "by" | "for" | "in" | "on" => Prepostion,
+        "is" | "are" => Verb,
+        "if" => If,
+        "who" | "what" => Pronoun,
+        "not" => Not,
+        "all" | "every" | "each" => Quantifier,
+        _ => {
+            // If the last letter is uppercase. Letters without case, as in many non-Latin scripts, make literals.
+            if word
+                .chars()
+                .rfind(|c| c.is_alphabetic())
+                .is_some_and(char::is_uppercase)
+            {
+                Variable
+            } else {
+                Literal
+            }
+        }
+    }
+}
+
+/// Consumes the rest of a word, returning the byte offset of its end.
+fn word_end(chars: &mut Peekable<CharIndices>, mut end: usize) -> usize {
+    while let Some((i, c)) = chars.next_if(|&(_, c)| valid_iden(c)) {
+        end = i + c.len_utf8();
     }
 
-    String::from(&source[start..end])
+    end
+}
+
+/// Consumes any capitalised literals following a capitalised literal on the same line, so that proper names like 'New York' form a single literal.
+/// Returns the byte offset of the end of the name.
+fn proper_name_end(source: &str, chars: &mut Peekable<CharIndices>, mut end: usize) -> usize {
+    loop {
+        let mut lookahead = chars.clone();
+        while lookahead.next_if(|&(_, c)| c == ' ' || c == '\t').is_some() {}
+
+        let Some((start, c)) = lookahead.next_if(|&(_, c)| c.is_uppercase()) else {
+            return end;
+        };
+        let word_end = word_end(&mut lookahead, start + c.len_utf8());
+        if word_kind(&source[start..word_end]) != Literal {
+            return end;
+        }
+
+        *chars = lookahead;
+        end = word_end;
+    }
 }
 
-/// Finds the next newline in the given source string.
-fn find_newline(source: &str) -> usize {
-    let mut i = 0;
-    while &source[i..i + 1] != "\n" {
-        i += 1
+/// Consumes the rest of a number, which may have a fractional part, returning the byte offset of its end.
+fn number_end(chars: &mut Peekable<CharIndices>, mut end: usize) -> usize {
+    while let Some((i, c)) = chars.next_if(|&(_, c)| c.is_ascii_digit()) {
+        end = i + c.len_utf8();
+    }
+
+    // A full stop is only a decimal point if a digit follows it
+    let mut lookahead = chars.clone();
+    if lookahead.next_if(|&(_, c)| c == '.').is_some()
+        && lookahead.peek().is_some_and(|&(_, c)| c.is_ascii_digit())
+    {
+        *chars = lookahead;
+        while let Some((i, c)) = chars.next_if(|&(_, c)| c.is_ascii_digit()) {
+            end = i + c.len_utf8();
+        }
     }
 
-    i
+    end
+}
+
+/// Consumes the rest of a quoted name, which can't span multiple lines.
+/// Returns the byte offset of the end of the name, or an error containing the byte offset of the end of the line if the name is unterminated.
+fn quoted_end(chars: &mut Peekable<CharIndices>, mut end: usize) -> Result<usize, usize> {
+    let mut escaped = false;
+    while let Some((i, c)) = chars.next_if(|&(_, c)| c != '\n') {
+        end = i + c.len_utf8();
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Ok(end),
+            _ => {}
+        }
+    }
+
+    Err(end)
 }
 
 /// Scans the given source string, converting it into a series of tokens.
+/// Returns an error for the first character that can't be scanned.
 pub fn scan(source: &str) -> Result<Vec<Token>, ParseError> {
+    let tokens = tokenise(source);
+    match tokens.iter().find(|token| token.kind() == TokenType::Error) {
+        Some(token) => Err(ParseError::from(token.clone())),
+        None => Ok(tokens),
+    }
+}
+
+/// Scans the given source string, converting it into a series of tokens.
+/// Any character that can't be scanned becomes an error token, and scanning continues after it.
+pub fn tokenise(source: &str) -> Vec<Token> {
+    let _enter = debug_span!("scan", length = source.len()).entered();
     let mut tokens = Vec::new();
 
-    let mut i = 0;
-    while i < source.len() {
-        let c = source.chars().nth(i).unwrap();
+    let mut chars = source.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
         match c {
-            c if c.is_whitespace() => {
-                // Ignore and move to next character
-                i += 1;
-                continue;
-            }
+            c if c.is_whitespace() => {} // Ignore and move to next character
             '(' => tokens.push(Token::new(LeftParen, "(", i)),
             ')' => tokens.push(Token::new(RightParen, ")", i)),
             '.' => tokens.push(Token::new(FullStop, ".", i)),
             '?' => tokens.push(Token::new(QuestionMark, "?", i)),
             '#' => {
                 // Comment, skip to next line
-                i = find_newline(&source[i..]);
-                continue;
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            }
+            '"' => match quoted_end(&mut chars, i + 1) {
+                Ok(end) => tokens.push(Token::new(Literal, &source[i..end], i)),
+                Err(end) => {
+                    debug!(start = i, "unterminated quoted name");
+                    tokens.push(Token::new(TokenType::Error, &source[i..end], i));
+                }
+            },
+            c if c.is_ascii_digit() => {
+                let end = number_end(&mut chars, i + 1);
+                tokens.push(Token::new(Number, &source[i..end], i));
             }
             c if c.is_alphabetic() => {
-                let lexeme = get_word(source, i);
-                let length = lexeme.len();
-                let kind = match lexeme.to_lowercase().as_str() {
-                    "a" | "an" | "the" => Article,
-                    "and" | "or" => Operator,
-                    "of" | "to" => Prepostion,
-                    "is" | "are" => Verb,
-                    "if" => If,
-                    "who" | "what" => Pronoun,
-                    "not" => Not,
-                    _ => {
-                        // If the last character is lowercase
-                        if lexeme.chars().nth(length - 1).unwrap().is_lowercase() {
-                            Literal
-                        } else {
-                            Variable
-                        }
-                    }
-                };
-                tokens.push(Token::new(kind, &lexeme, i));
-
-                i += lexeme.len();
-                continue;
+                let mut end = word_end(&mut chars, i + c.len_utf8());
+                let kind = word_kind(&source[i..end]);
+                if kind == Literal && c.is_uppercase() {
+                    end = proper_name_end(source, &mut chars, end);
+                }
+
+                tokens.push(Token::new(kind, &source[i..end], i));
+            }
+            _ => {
+                debug!(character = %c, start = i, "unexpected character");
+                tokens.push(Token::new(TokenType::Error, &c.to_string(), i));
             }
-            _ => Err(ParseError::from(Token::new(
-                TokenType::Error,
-                &c.to_string(),
-                i,
-            )))?,
         }
-
-        i += 1;
     }
 
-    tokens.push(Token::new(TokenType::Eof, "", i));
-    Ok(tokens)
+    tokens.push(Token::new(TokenType::Eof, "", source.len()));
+    debug!(tokens = tokens.len(), "finished scanning");
+    tokens
 }
 
 #[cfg(test)]
@@ -978,3 +978,202 @@ fn program_2() {
         ]),
     );
 }
+
+#[test]
+fn comment() {
+    test_tokens_equal(
+        "# A comment\nA hamster is a mammal. # Another comment",
+        HashSet::from([
+            Token {
+                kind: Article,
+                lexeme: String::from("A"),
+                start: 12,
+            },
+            Token {
+                kind: Literal,
+                lexeme: String::from("hamster"),
+                start: 14,
+            },
+            Token {
+                kind: Verb,
+                lexeme: String::from("is"),
+                start: 22,
+            },
+            Token {
+                kind: Article,
+                lexeme: String::from("a"),
+                start: 25,
+            },
+            Token {
+                kind: Literal,
+                lexeme: String::from("mammal"),
+                start: 27,
+            },
+            Token {
+                kind: FullStop,
+                lexeme: String::from("."),
+                start: 33,
+            },
+            Token {
+                kind: Eof,
+                lexeme: String::from(""),
+                start: 53,
+            },
+        ]),
+    );
+}
+
+/// Scans a source string, keeping the start of each token.
+fn tokens_with_starts(source: &str) -> Vec<(TokenType, String, usize)> {
+    tokenise(source)
+        .into_iter()
+        .map(|token| (token.kind, token.lexeme, token.start))
+        .collect()
+}
+
+#[test]
+fn unicode_identifiers() {
+    assert_eq!(
+        tokens_with_starts("Zoë is the sister of José. Ångström is a unit. ÉÀ is 张伟."),
+        vec![
+            (Literal, String::from("Zoë"), 0),
+            (Verb, String::from("is"), 5),
+            (Article, String::from("the"), 8),
+            (Literal, String::from("sister"), 12),
+            (Prepostion, String::from("of"), 19),
+            (Literal, String::from("José"), 22),
+            (FullStop, String::from("."), 27),
+            (Literal, String::from("Ångström"), 29),
+            (Verb, String::from("is"), 40),
+            (Article, String::from("a"), 43),
+            (Literal, String::from("unit"), 45),
+            (FullStop, String::from("."), 49),
+            (Variable, String::from("ÉÀ"), 51),
+            (Verb, String::from("is"), 56),
+            (Literal, String::from("张伟"), 59),
+            (FullStop, String::from("."), 65),
+            (Eof, String::from(""), 66),
+        ]
+    );
+}
+
+#[test]
+fn unicode_error() {
+    let source = "Zoë is a café ☕.";
+    let tokens = tokens_with_starts(source);
+
+    assert_eq!(tokens[4], (Error, String::from("☕"), 16));
+    assert_eq!(&source[16..], "☕.");
+    assert_eq!(tokens[5], (FullStop, String::from("."), 19));
+}
+
+proptest::proptest! {
+    #[test]
+    fn token_starts(source in "\\PC{0,64}") {
+        for token in tokenise(&source) {
+            proptest::prop_assert!(source[token.start()..].starts_with(token.lexeme()));
+        }
+    }
+}
+
+#[test]
+fn proper_names() {
+    assert_eq!(
+        tokens_with_starts("Mary Anne is the capital of\tNew York State.\nJohn\nSmith is Bob X."),
+        vec![
+            (Literal, String::from("Mary Anne"), 0),
+            (Verb, String::from("is"), 10),
+            (Article, String::from("the"), 13),
+            (Literal, String::from("capital"), 17),
+            (Prepostion, String::from("of"), 25),
+            (Literal, String::from("New York State"), 28),
+            (FullStop, String::from("."), 42),
+            (Literal, String::from("John"), 44),
+            (Literal, String::from("Smith"), 49),
+            (Verb, String::from("is"), 55),
+            (Literal, String::from("Bob"), 58),
+            (Variable, String::from("X"), 62),
+            (FullStop, String::from("."), 63),
+            (Eof, String::from(""), 64),
+        ]
+    );
+}
+
+#[test]
+fn quoted_names() {
+    assert_eq!(
+        tokens_with_starts(r#""New York" is "a \"city\"". ""#),
+        vec![
+            (Literal, String::from(r#""New York""#), 0),
+            (Verb, String::from("is"), 11),
+            (Literal, String::from(r#""a \"city\"""#), 14),
+            (FullStop, String::from("."), 26),
+            (Error, String::from("\""), 28),
+            (Eof, String::from(""), 29),
+        ]
+    );
+}
+
+#[test]
+fn numbers() {
+    assert_eq!(
+        tokens_with_starts("Alice is 34 years old. Pi is 3.14. X is 2."),
+        vec![
+            (Literal, String::from("Alice"), 0),
+            (Verb, String::from("is"), 6),
+            (Number, String::from("34"), 9),
+            (Literal, String::from("years"), 12),
+            (Literal, String::from("old"), 18),
+            (FullStop, String::from("."), 21),
+            (Literal, String::from("Pi"), 23),
+            (Verb, String::from("is"), 26),
+            (Number, String::from("3.14"), 29),
+            (FullStop, String::from("."), 33),
+            (Variable, String::from("X"), 35),
+            (Verb, String::from("is"), 37),
+            (Number, String::from("2"), 40),
+            (FullStop, String::from("."), 41),
+            (Eof, String::from(""), 42),
+        ]
+    );
+}
+
+#[test]
+fn prepositions() {
+    assert_eq!(
+        tokens_with_starts("A book is in the box on Monday."),
+        vec![
+            (Article, String::from("A"), 0),
+            (Literal, String::from("book"), 2),
+            (Verb, String::from("is"), 7),
+            (Prepostion, String::from("in"), 10),
+            (Article, String::from("the"), 13),
+            (Literal, String::from("box"), 17),
+            (Prepostion, String::from("on"), 21),
+            (Literal, String::from("Monday"), 24),
+            (FullStop, String::from("."), 30),
+            (Eof, String::from(""), 31),
+        ]
+    );
+}
+
+#[test]
+fn quantifiers() {
+    assert_eq!(
+        tokens_with_starts("All hamsters are mammals. Every X, each Y."),
+        vec![
+            (Quantifier, String::from("All"), 0),
+            (Literal, String::from("hamsters"), 4),
+            (Verb, String::from("are"), 13),
+            (Literal, String::from("mammals"), 17),
+            (FullStop, String::from("."), 24),
+            (Quantifier, String::from("Every"), 26),
+            (Variable, String::from("X"), 32),
+            (Error, String::from(","), 33),
+            (Quantifier, String::from("each"), 35),
+            (Variable, String::from("Y"), 40),
+            (FullStop, String::from("."), 41),
+            (Eof, String::from(""), 42),
+        ]
+    );
+}
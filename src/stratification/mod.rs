@@ -0,0 +1,259 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+use tracing::{debug, debug_span};
+
+use crate::parser::{ast, Location};
+
+/// The name and arity of a relationship.
+pub(crate) type Relationship = (String, usize);
+
+/// An error raised when a relationship depends on its own negation, so that it has no stratification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StratificationError {
+    relationship: String,
+    statements: Vec<(ast::Stmt, Location)>,
+}
+
+impl StratificationError {
+    /// The name of a relationship that depends on its own negation.
+    pub fn relationship(&self) -> &str {
+        &self.relationship
+    }
+
+    /// The locations of the statements that make the relationship depend on its own negation, in the order they were given.
+    pub fn locations(&self) -> impl Iterator<Item = Location> + '_ {
+        self.statements.iter().map(|&(_, location)| location)
+    }
+}
+
+impl fmt::Display for StratificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' depends on its own negation in these statements:",
+            self.relationship
+        )?;
+        for (statement, location) in &self.statements {
+            write!(f, "\n    {location}: {statement}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for StratificationError {}
+
+/// Gets the name and arity of the relationship in a statement or clause.
+fn relationship(relationship: &ast::Identifier, right: &[ast::Identifier]) -> Relationship {
+    (relationship.name(), 1 + right.len())
+}
+
+/// Collects the relationships that a clause depends on, and whether each dependency is negated.
+fn dependencies(clause: &ast::Clause, output: &mut Vec<(Relationship, bool)>) {
+    match clause {
+        ast::Clause::Simple {
+            negated,
+            relationship: name,
+            right,
+            ..
+        } => {
+            // Comparisons are built in, so they can't depend on anything
+            if ast::Comparison::from_relationship(name, right).is_none() {
+                output.push((relationship(name, right), *negated));
+            }
+        }
+        ast::Clause::Operator { clauses, .. } => {
+            for clause in clauses {
+                dependencies(clause, output);
+            }
+        }
+    }
+}
+
+/// An edge in the dependency graph, from the relationship defined by a rule to a relationship in its condition.
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    to: usize,
+    negated: bool,
+    statement: usize,
+}
+
+/// The graph of which relationships each relationship depends on.
+#[derive(Debug, Default)]
+struct Graph {
+    nodes: Vec<Relationship>,
+    indices: HashMap<Relationship, usize>,
+    edges: Vec<Vec<Edge>>,
+}
+
+impl Graph {
+    fn node(&mut self, relationship: Relationship) -> usize {
+        if let Some(&index) = self.indices.get(&relationship) {
+            return index;
+        }
+
+        self.nodes.push(relationship.clone());
+        self.edges.push(Vec::new());
+        self.indices.insert(relationship, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    /// Finds the strongly connected components of the graph with Tarjan's algorithm.
+    /// Each component comes after every component it depends on.
+    fn components(&self) -> Vec<Vec<usize>> {
+        const UNVISITED: usize = usize::MAX;
+
+        let mut indices = vec![UNVISITED; self.nodes.len()];
+        let mut low_links = vec![0; self.nodes.len()];
+        let mut on_stack = vec![false; self.nodes.len()];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+
+        for root in 0..self.nodes.len() {
+            if indices[root] != UNVISITED {
+                continue;
+            }
+
+            // Each frame holds a node and the position of the next edge to follow from it
+            let mut frames = vec![(root, 0)];
+            indices[root] = next_index;
+            low_links[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some(&mut (node, ref mut edge)) = frames.last_mut() {
+                if let Some(&Edge { to, .. }) = self.edges[node].get(*edge) {
+                    *edge += 1;
+                    if indices[to] == UNVISITED {
+                        indices[to] = next_index;
+                        low_links[to] = next_index;
+                        next_index += 1;
+                        stack.push(to);
+                        on_stack[to] = true;
+                        frames.push((to, 0));
+                    } else if on_stack[to] {
+                        low_links[node] = low_links[node].min(indices[to]);
+                    }
+                    continue;
+                }
+
+                frames.pop();
+                if let Some(&(parent, _)) = frames.last() {
+                    low_links[parent] = low_links[parent].min(low_links[node]);
+                }
+
+                if low_links[node] == indices[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+
+        components
+    }
+}
+
+/// Pairs each rule parsed from 'source' with its location, so that it can be stratified along with rules from other sources.
+pub(crate) fn rules<'a>(
+    source: &str,
+    trees: impl IntoIterator<Item = &'a ast::Stmt>,
+) -> Vec<(ast::Stmt, Location)> {
+    let rules: Vec<_> = trees
+        .into_iter()
+        .filter(|tree| tree.kind() == ast::StmtType::Rule)
+        .cloned()
+        .collect();
+    let locations = Location::of_each(source, rules.iter().map(|rule| rule.span().start));
+
+    rules.into_iter().zip(locations).collect()
+}
+
+/// Assigns each relationship defined by a rule to a stratum, so that every relationship it depends on is in the same or an earlier stratum,
+/// and every relationship it depends on negatively is in an earlier stratum.
+/// Returns an error naming the statements involved, with their locations, if a relationship depends on its own negation.
+pub(crate) fn stratify<'a>(
+    trees: impl IntoIterator<Item = &'a (ast::Stmt, Location)>,
+) -> Result<HashMap<Relationship, usize>, StratificationError> {
+    let _enter = debug_span!("stratify").entered();
+
+    let mut graph = Graph::default();
+    let mut rules = Vec::new();
+    for tree @ (statement, _) in trees {
+        let Some(condition) = statement.condition() else {
+            continue;
+        };
+
+        let from = graph.node(relationship(statement.relationship(), statement.right()));
+        let mut output = Vec::new();
+        dependencies(condition, &mut output);
+        for (relationship, negated) in output {
+            let to = graph.node(relationship);
+            graph.edges[from].push(Edge {
+                to,
+                negated,
+                statement: rules.len(),
+            });
+        }
+        rules.push(tree);
+    }
+
+    let components = graph.components();
+    let mut component_of = vec![0; graph.nodes.len()];
+    for (i, component) in components.iter().enumerate() {
+        for &node in component {
+            component_of[node] = i;
+        }
+    }
+
+    let mut strata = vec![0; components.len()];
+    for (i, component) in components.iter().enumerate() {
+        let edges = component
+            .iter()
+            .flat_map(|&node| graph.edges[node].iter().map(move |edge| (node, edge)));
+
+        for (node, edge) in edges {
+            let to = component_of[edge.to];
+            if to != i {
+                strata[i] = strata[i].max(strata[to] + edge.negated as usize);
+            } else if edge.negated {
+                // Every rule in the cycle is involved
+                let mut statements: Vec<_> = component
+                    .iter()
+                    .flat_map(|&node| &graph.edges[node])
+                    .filter(|edge| component_of[edge.to] == i)
+                    .map(|edge| edge.statement)
+                    .collect();
+                statements.sort();
+                statements.dedup();
+
+                debug!(relationship = graph.nodes[node].0, "unstratifiable");
+                return Err(StratificationError {
+                    relationship: graph.nodes[node].0.clone(),
+                    statements: statements
+                        .into_iter()
+                        .map(|statement| rules[statement].clone())
+                        .collect(),
+                });
+            }
+        }
+    }
+
+    Ok(graph
+        .nodes
+        .into_iter()
+        .zip(component_of)
+        .map(|(relationship, component)| (relationship, strata[component]))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests;
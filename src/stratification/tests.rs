@@ -0,0 +1,65 @@
+use super::*;
+use crate::parser;
+
+fn stratify(source: &str) -> Result<HashMap<Relationship, usize>, StratificationError> {
+    let trees = parser::parse_source(source).unwrap();
+    super::stratify(&rules(source, &trees))
+}
+
+fn relationship(name: &str, arity: usize) -> Relationship {
+    (name.to_string(), arity)
+}
+
+#[test]
+fn stratify_positive() {
+    assert_eq!(
+        stratify("X is the ancestor of Y if X is the ancestor of Z and Z is the parent of Y.")
+            .unwrap(),
+        HashMap::from([
+            (relationship("ancestor", 2), 0),
+            (relationship("parent", 2), 0),
+        ])
+    );
+}
+
+#[test]
+fn stratify_negation() {
+    assert_eq!(
+        stratify(
+            "X is the sibling of Y if Z is the parent of X and Z is the parent of Y and X is not Y. X is lonely if X is the parent of Y and X is not the sibling of Y."
+        )
+        .unwrap(),
+        HashMap::from([
+            (relationship("sibling", 2), 0),
+            (relationship("parent", 2), 0),
+            (relationship("lonely", 1), 1),
+        ])
+    );
+}
+
+#[test]
+fn stratify_self_negation() {
+    let err = stratify("Ann is one. X is odd if X is one and X is not odd.").unwrap_err();
+
+    assert_eq!(err.relationship(), "odd");
+    assert_eq!(
+        err.to_string(),
+        "'odd' depends on its own negation in these statements:\n    line 1, column 13: X is odd if X is one and X is not odd."
+    );
+}
+
+#[test]
+fn stratify_negative_cycle() {
+    let err = stratify(
+        "X is even if X is a number and X is not odd.\nX is small if X is one. X is odd if X is a number and X is not even.",
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "'{}' depends on its own negation in these statements:\n    line 1, column 1: X is even if X is a number and X is not odd.\n    line 2, column 25: X is odd if X is a number and X is not even.",
+            err.relationship()
+        )
+    );
+}
@@ -1,7 +1,15 @@
+use std::{collections::HashMap, error::Error, fmt, ops::Range};
+
+use tracing::{debug, debug_span};
+
 use crate::parser::ast::{self, IdenType};
 
+#[cfg(any(test, fuzzing))]
+pub mod syntax;
+
 /// An identifier defined in Co-log, with its article and preposition, and the name used to refer to it in Prolog.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Identifier {
     cl_name: String,
     pl_name: String,
@@ -19,35 +27,100 @@ impl Identifier {
     }
 }
 
+/// How the transpiler names identifiers in the Prolog that it generates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Naming {
+    /// Numbered names such as `l1` and `V1`, which can only be read with the identifier table.
+    #[default]
+    Numbered,
+    /// Names taken from Co-log, such as `co_brother(john, jack)`, escaped so that they are valid Prolog and never clash.
+    /// Relationships are prefixed with `co_`, so that they can't redefine one of Prolog's builtin predicates.
+    Readable,
+}
+
+/// The prefix of every readable relationship name, which no builtin Prolog predicate starts with.
+const READABLE_PREFIX: &str = "co_";
+
 /// A set of identifiers defined in a co-log program.
+/// Variables and pronouns aren't included, as they only exist within the statement that uses them.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "Table", try_from = "Table")
+)]
 pub struct Identifiers {
     identifiers: Vec<Identifier>,
-    highest_literal: u16,
-    highest_variable: u16,
+    /// The position of each identifier by its Co-log name, keeping the first if a name is used twice.
+    by_cl_name: HashMap<String, usize>,
+    /// The position of each identifier by its Prolog name.
+    by_pl_name: HashMap<String, usize>,
+    highest_literal: u32,
+    naming: Naming,
 }
 
 impl Identifiers {
-    fn new() -> Self {
-        let identifiers = vec![Identifier {
-            cl_name: "eq".to_string(),
-            pl_name: "eq".to_string(),
-            article: None,
-            preposition: None,
-        }];
+    pub(crate) fn new() -> Self {
+        Self::with_naming(Naming::Numbered)
+    }
+
+    /// Creates a set of identifiers that will be named in Prolog according to 'naming'.
+    pub fn with_naming(naming: Naming) -> Self {
+        let mut identifiers = Self::empty(naming);
+        for comparison in ast::Comparison::ALL {
+            identifiers.push(Identifier {
+                cl_name: comparison.name().to_string(),
+                pl_name: comparison.name().to_string(),
+                article: None,
+                preposition: None,
+            });
+        }
+
+        identifiers
+    }
+
+    /// Creates a set of identifiers without even the builtins.
+    fn empty(naming: Naming) -> Self {
         Identifiers {
-            identifiers,
+            identifiers: Vec::new(),
+            by_cl_name: HashMap::new(),
+            by_pl_name: HashMap::new(),
             highest_literal: 0,
-            highest_variable: 0,
+            naming,
         }
     }
 
+    /// Adds an identifier to the array and to the indexes used to look it up.
+    fn push(&mut self, identifier: Identifier) {
+        let position = self.identifiers.len();
+        self.by_cl_name
+            .entry(identifier.cl_name.clone())
+            .or_insert(position);
+        self.by_pl_name
+            .entry(identifier.pl_name.clone())
+            .or_insert(position);
+
+        self.identifiers.push(identifier);
+    }
+
     /// Given a co-log identifier's name, get its Prolog name.
+    /// Co-log names are always lowercase, so names are found whatever case they are written in.
     fn get_from_cl_name(&self, cl_name: &str) -> Option<&Identifier> {
-        self.identifiers.iter().find(|&identifier| {
-            is_lowercase(&identifier.cl_name[0..1]) && identifier.cl_name == cl_name.to_lowercase()
-                || !is_lowercase(&identifier.cl_name[0..1]) && identifier.cl_name == cl_name
-        })
+        self.by_cl_name
+            .get(&cl_name.to_lowercase())
+            .map(|&position| &self.identifiers[position])
+    }
+
+    /// Given an identifier's Prolog name, get its co-log name.
+    pub fn get_from_pl_name(&self, pl_name: &str) -> Option<&Identifier> {
+        self.by_pl_name
+            .get(pl_name)
+            .map(|&position| &self.identifiers[position])
     }
 
     /// Adds a new identifier to the array.
@@ -55,22 +128,31 @@ impl Identifiers {
     fn add(&mut self, identifier: &ast::Identifier) -> &str {
         let (pl_name, cl_name) = match identifier.kind() {
             IdenType::Variable | IdenType::Pronoun => {
-                self.highest_variable += 1;
-                (
-                    "V".to_string() + &(self.highest_variable).to_string(),
-                    identifier.lexeme().to_string(),
-                )
+                unreachable!("variables are named by the scope of their statement")
             }
-            IdenType::Literal => {
-                self.highest_literal += 1;
-                (
-                    "l".to_string() + &(self.highest_literal).to_string(),
-                    identifier.lexeme().to_lowercase(),
-                )
+            // Numbers are written as they are, so that Prolog can compare them
+            IdenType::Number => (
+                identifier.lexeme().to_string(),
+                identifier.lexeme().to_string(),
+            ),
+            IdenType::Literal | IdenType::Verb | IdenType::Plural => {
+                let cl_name = identifier.name();
+                let pl_name = match self.naming {
+                    Naming::Numbered => {
+                        self.highest_literal += 1;
+                        "l".to_string() + &(self.highest_literal).to_string()
+                    }
+                    Naming::Readable => {
+                        unique(sanitise(&cl_name, "l", char::is_lowercase), |name| {
+                            self.get_from_pl_name(name).is_some()
+                        })
+                    }
+                };
+                (pl_name, cl_name)
             }
         };
 
-        self.identifiers.push(Identifier {
+        self.push(Identifier {
             cl_name,
             pl_name,
             article: identifier.article().clone(),
@@ -80,11 +162,29 @@ impl Identifiers {
         &self.identifiers.last().unwrap().pl_name
     }
 
+    /// Gets the Prolog name of a relationship, creating it if it doesn't exist.
+    /// A plural noun is found by its singular form, so that 'mammals' in `All hamsters are mammals.` and 'mammal' are the same relationship.
+    fn get_or_create_relationship(&mut self, relationship: &ast::Identifier) -> String {
+        let existing = self.get_from_cl_name(&relationship.name());
+
+        let pl_name = match existing {
+            Some(identifier) => identifier.pl_name.clone(),
+            None => self.add(relationship).to_string(),
+        };
+
+        // The builtin comparisons are defined by the prelude under their own names
+        let builtin = ast::Comparison::ALL
+            .iter()
+            .any(|comparison| comparison.name() == pl_name);
+        match self.naming {
+            Naming::Readable if !builtin => format!("{READABLE_PREFIX}{pl_name}"),
+            _ => pl_name,
+        }
+    }
+
     /// Gets the Prolog name of an identifier, creating it if it doesn't exist.
     fn get_or_create(&mut self, identifier: &ast::Identifier) -> String {
-        if identifier.kind() == ast::IdenType::Pronoun {
-            self.add(identifier).to_string()
-        } else if let Some(identifier) = self.get_from_cl_name(identifier.lexeme()) {
+        if let Some(identifier) = self.get_from_cl_name(identifier.lexeme()) {
             identifier.pl_name.clone()
         } else {
             self.add(identifier).to_string()
@@ -94,60 +194,420 @@ impl Identifiers {
     pub fn identifiers(&self) -> &Vec<Identifier> {
         &self.identifiers
     }
+
+    /// Saves the identifiers as JSON, so that a program can be transpiled over many sessions and keep the same Prolog names.
+    #[cfg(feature = "serde")]
+    pub fn save(&self, writer: impl std::io::Write) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(writer, self)
+    }
+
+    /// Loads identifiers saved by 'save', returning an error if they aren't valid.
+    #[cfg(feature = "serde")]
+    pub fn load(reader: impl std::io::Read) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+}
+
+/// The form that identifiers are saved in, which is checked when it is loaded.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Table {
+    naming: Naming,
+    identifiers: Vec<Identifier>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Identifiers> for Table {
+    fn from(value: Identifiers) -> Self {
+        Table {
+            naming: value.naming,
+            identifiers: value.identifiers,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<Table> for Identifiers {
+    type Error = IdentifierError;
+
+    fn try_from(value: Table) -> Result<Self, Self::Error> {
+        let mut identifiers = Identifiers::try_from(value.identifiers)?;
+        identifiers.naming = value.naming;
+        Ok(identifiers)
+    }
+}
+
+/// An error in a set of identifiers that is being loaded, which would make the transpiler generate wrong or invalid Prolog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdentifierError {
+    /// An identifier's Prolog name isn't a number or an unquoted atom.
+    InvalidName(String),
+
+    /// More than one identifier has the given Prolog name.
+    DuplicatePlName(String),
+
+    /// More than one identifier has the given Co-log name.
+    DuplicateClName(String),
+
+    /// The given builtin comparison is missing, or isn't named the same in Prolog.
+    Builtin(String),
+
+    /// The given number isn't named the same in Co-log and Prolog, so Prolog couldn't compare it.
+    Number(String),
+
+    /// The given Co-log name of a literal isn't lowercase, so the transpiler would never find it.
+    UppercaseClName(String),
+}
+
+impl fmt::Display for IdentifierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentifierError::InvalidName(name) => write!(f, "'{name}' is not a valid Prolog name"),
+            IdentifierError::DuplicatePlName(name) => {
+                write!(f, "the Prolog name '{name}' is used more than once")
+            }
+            IdentifierError::DuplicateClName(name) => {
+                write!(f, "the Co-log name '{name}' is used more than once")
+            }
+            IdentifierError::Builtin(name) => {
+                write!(f, "the builtin '{name}' must be named '{name}' in Prolog")
+            }
+            IdentifierError::Number(name) => {
+                write!(
+                    f,
+                    "the number '{name}' must be named '{name}' in Co-log and Prolog"
+                )
+            }
+            IdentifierError::UppercaseClName(name) => {
+                write!(f, "the Co-log name '{name}' must be lowercase")
+            }
+        }
+    }
+}
+
+impl Error for IdentifierError {}
+
+/// Checks if a name can be written into Prolog as it is, as a number or an unquoted atom.
+fn valid_pl_name(name: &str) -> bool {
+    let atom = name.starts_with(char::is_lowercase)
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+
+    is_number(name) || atom
+}
+
+/// Checks if a name is a number, which is named the same in Co-log and Prolog.
+fn is_number(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && name.parse::<f64>().is_ok()
+}
+
+/// Names the variables within a single statement, as Prolog scopes variables to a clause.
+#[derive(Debug)]
+struct Scope {
+    variables: Vec<Identifier>,
+    /// The readable names of the variables written in the statement, which its quantified variable must not take.
+    reserved: Vec<String>,
+    naming: Naming,
+}
+
+impl Scope {
+    /// Creates the scope of a statement.
+    fn new(naming: Naming, tree: &ast::Stmt) -> Self {
+        let mut variables = Vec::new();
+        variables.extend(std::iter::once(tree.left()).chain(tree.right()));
+        if let Some(condition) = tree.condition() {
+            clause_identifiers(condition, &mut variables);
+        }
+
+        Scope {
+            variables: Vec::new(),
+            reserved: variables
+                .into_iter()
+                .filter(|identifier| identifier.kind() == IdenType::Variable)
+                .map(|identifier| readable_variable(identifier.lexeme()))
+                .collect(),
+            naming,
+        }
+    }
+
+    /// Gets the Prolog name of a variable or pronoun, naming it if it is new.
+    fn get_or_create(&mut self, identifier: &ast::Identifier) -> String {
+        // Each pronoun is a separate variable, even if the same pronoun is used twice, so a repeated pronoun is numbered like 'who_2'
+        if identifier.kind() == IdenType::Variable {
+            if let Some(variable) = self
+                .variables
+                .iter()
+                .find(|variable| variable.cl_name == identifier.lexeme())
+            {
+                return variable.pl_name.clone();
+            }
+        }
+
+        let cl_name = match identifier.kind() {
+            IdenType::Pronoun => unique(identifier.lexeme().to_lowercase(), |name| {
+                self.variables
+                    .iter()
+                    .any(|variable| variable.cl_name == name)
+            }),
+            _ => identifier.lexeme().to_string(),
+        };
+        let pl_name = match self.naming {
+            Naming::Numbered => format!("V{}", self.variables.len() + 1),
+            Naming::Readable => {
+                // The quantified variable can't be written in Co-log, so it is named like one that the statement doesn't use
+                let name = if cl_name == ast::QUANTIFIED_VARIABLE {
+                    unique(String::from("X"), |name| {
+                        self.reserved.iter().any(|reserved| reserved == name)
+                    })
+                } else {
+                    readable_variable(&cl_name)
+                };
+                unique(name, |name| {
+                    self.variables
+                        .iter()
+                        .any(|variable| variable.pl_name == name)
+                })
+            }
+        };
+
+        self.variables.push(Identifier {
+            cl_name,
+            pl_name: pl_name.clone(),
+            article: None,
+            preposition: None,
+        });
+        pl_name
+    }
+}
+
+/// Collects the identifiers on either side of the relationships in a clause.
+fn clause_identifiers<'a>(clause: &'a ast::Clause, output: &mut Vec<&'a ast::Identifier>) {
+    match clause {
+        ast::Clause::Simple { left, right, .. } => {
+            output.extend(std::iter::once(left).chain(right));
+        }
+        ast::Clause::Operator { clauses, .. } => {
+            for clause in clauses {
+                clause_identifiers(clause, output);
+            }
+        }
+    }
+}
+
+/// Gets the readable Prolog name of a variable, which is capitalised as Prolog variables must be.
+fn readable_variable(cl_name: &str) -> String {
+    let mut chars = cl_name.chars();
+    let capitalised = chars.next().into_iter().flat_map(char::to_uppercase);
+    sanitise(
+        &capitalised.chain(chars).collect::<String>(),
+        "V",
+        char::is_uppercase,
+    )
+}
+
+/// Replaces each character of a name that can't be part of an unquoted Prolog name with an underscore.
+/// The prefix is added if the name doesn't start with a valid first character, such as an uppercase letter for a variable.
+fn sanitise(name: &str, prefix: &str, valid_start: fn(char) -> bool) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if name.starts_with(valid_start) {
+        name
+    } else {
+        prefix.to_string() + &name
+    }
+}
+
+/// Numbers a name if it is already taken, so that 'warm blooded' becomes `warm_blooded_2` after 'warm-blooded'.
+pub(crate) fn unique(name: String, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(&name) {
+        return name;
+    }
+
+    (2..)
+        .map(|number| format!("{name}_{number}"))
+        .find(|name| !taken(name))
+        .unwrap()
 }
 
-impl From<Vec<Identifier>> for Identifiers {
-    fn from(identifiers: Vec<Identifier>) -> Self {
-        let mut highest_literal = 0;
-        let mut highest_variable = 0;
+impl TryFrom<Vec<Identifier>> for Identifiers {
+    type Error = IdentifierError;
+
+    /// Rebuilds a set of numbered identifiers, checking that the transpiler can keep adding to them.
+    fn try_from(identifiers: Vec<Identifier>) -> Result<Self, Self::Error> {
+        let mut table = Self::empty(Naming::Numbered);
+
+        for identifier in identifiers {
+            if !valid_pl_name(&identifier.pl_name) {
+                return Err(IdentifierError::InvalidName(identifier.pl_name));
+            }
+            if table.by_pl_name.contains_key(&identifier.pl_name) {
+                return Err(IdentifierError::DuplicatePlName(identifier.pl_name));
+            }
+            if table.by_cl_name.contains_key(&identifier.cl_name) {
+                return Err(IdentifierError::DuplicateClName(identifier.cl_name));
+            }
+            if is_number(&identifier.pl_name) || is_number(&identifier.cl_name) {
+                if identifier.pl_name != identifier.cl_name {
+                    let number = if is_number(&identifier.pl_name) {
+                        identifier.pl_name
+                    } else {
+                        identifier.cl_name
+                    };
+                    return Err(IdentifierError::Number(number));
+                }
+            } else if identifier.cl_name != identifier.cl_name.to_lowercase() {
+                return Err(IdentifierError::UppercaseClName(identifier.cl_name));
+            }
 
-        for identifier in &identifiers {
-            match &identifier.pl_name.as_str()[0..1] {
-                "V" => highest_variable = identifier.pl_name[1..].parse::<u16>().unwrap(),
-                "l" => highest_literal = identifier.pl_name[1..].parse::<u16>().unwrap(),
-                _ => {} // Unreachable
+            // Builtins and numbers keep their own names, so they aren't numbered
+            let mut chars = identifier.pl_name.chars();
+            if let (Some('l'), Ok(number)) = (chars.next(), chars.as_str().parse::<u32>()) {
+                table.highest_literal = table.highest_literal.max(number);
             }
+
+            table.push(identifier);
         }
 
-        Self {
-            identifiers,
-            highest_literal,
-            highest_variable,
+        for comparison in ast::Comparison::ALL {
+            let name = comparison.name();
+            if table
+                .get_from_pl_name(name)
+                .is_none_or(|identifier| identifier.cl_name != name)
+            {
+                return Err(IdentifierError::Builtin(name.to_string()));
+            }
         }
+
+        Ok(table)
     }
 }
 
+/// The definitions that every generated Prolog program starts with.
+/// Arithmetic comparisons fail, rather than raising an exception, unless both sides are numbers.
+const PRELUDE: &str = ":- style_check(-discontiguous).
+eq(X, Y) :- X == Y.
+gt(X, Y) :- number(X), number(Y), X > Y.
+lt(X, Y) :- number(X), number(Y), X < Y.
+ge(X, Y) :- number(X), number(Y), X >= Y.
+le(X, Y) :- number(X), number(Y), X =< Y.
+";
+
+/// The clauses of the prelude that define the builtin comparisons, without their terminating full stops.
+#[cfg(feature = "swipl")]
+pub(crate) fn builtins() -> impl Iterator<Item = &'static str> {
+    PRELUDE
+        .lines()
+        .filter(|line| !line.starts_with(":-"))
+        .map(|line| line.strip_suffix('.').unwrap_or(line))
+}
+
 /// A query that can be used by the communicator module to query Prolog.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Query {
+    /// The kind of answer that the query asks for, which decides how its solutions are combined.
+    pub kind: ast::QueryType,
     pub relationship: String,
-    pub left: String,
-    pub right: Option<String>,
+    /// The Prolog names of the query's identifiers, starting with its left hand identifier.
+    pub arguments: Vec<String>,
+    /// The query's variables and pronouns, which aren't kept in the identifier table.
+    pub variables: Vec<Identifier>,
 }
 
-/// Checks if a string is lowercase.
-fn is_lowercase(s: &str) -> bool {
-    s.to_lowercase() == s
+impl Query {
+    /// Given the Prolog name of one of the query's variables, get its Co-log name.
+    pub fn get_variable(&self, pl_name: &str) -> Option<&str> {
+        self.variables
+            .iter()
+            .find(|variable| variable.pl_name == pl_name)
+            .map(|variable| variable.cl_name.as_str())
+    }
+}
+
+/// Links each clause of generated Prolog back to the Co-log statement that it was transpiled from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMap {
+    /// The line of the Prolog that the first clause is on, where the first line is 1.
+    first_line: usize,
+    /// The byte range in the Co-log source of the statement that each clause was transpiled from, in the order of the clauses.
+    spans: Vec<Range<usize>>,
+    /// The clauses after the prelude, without their terminating full stops.
+    clauses: Vec<String>,
+}
+
+impl SourceMap {
+    /// Gets the byte range in the Co-log source of the statement that was transpiled to the given line of Prolog, where the first line is 1.
+    /// Returns None for the lines of the prelude, which don't come from any statement.
+    pub fn statement_at(&self, line: usize) -> Option<Range<usize>> {
+        self.spans.get(line.checked_sub(self.first_line)?).cloned()
+    }
+
+    /// The clauses transpiled from the Co-log source, without their terminating full stops, each with the byte range of its statement.
+    pub fn clauses(&self) -> impl Iterator<Item = (Range<usize>, &str)> {
+        self.spans
+            .iter()
+            .cloned()
+            .zip(self.clauses.iter().map(String::as_str))
+    }
+}
+
+/// Transpiles an identifier to its Prolog name, looking variables up in the statement's scope and anything else in the identifier table.
+fn transpile_identifier(
+    identifier: &ast::Identifier,
+    identifiers: &mut Identifiers,
+    scope: &mut Scope,
+) -> String {
+    match identifier.kind() {
+        IdenType::Variable | IdenType::Pronoun => scope.get_or_create(identifier),
+        _ => identifiers.get_or_create(identifier),
+    }
+}
+
+/// Transpiles a relationship and its identifiers to a Prolog term, such as `l1(V1, l2, V2)`.
+fn transpile_term(
+    relationship: &ast::Identifier,
+    left: &ast::Identifier,
+    right: &[ast::Identifier],
+    identifiers: &mut Identifiers,
+    scope: &mut Scope,
+) -> String {
+    let mut term = format!("{}(", identifiers.get_or_create_relationship(relationship));
+    term += &transpile_identifier(left, identifiers, scope);
+    for identifier in right {
+        term += &format!(", {}", transpile_identifier(identifier, identifiers, scope));
+    }
+    term += ")";
+
+    term
 }
 
 /// Transpiles the clause of a query to Prolog.
-fn transpile_clause(clause: ast::Clause, identifiers: &mut Identifiers) -> String {
+fn transpile_clause(
+    clause: ast::Clause,
+    identifiers: &mut Identifiers,
+    scope: &mut Scope,
+) -> String {
     let mut output = String::new();
 
     match clause {
-        ast::Clause::Operator {
-            op_type,
-            left,
-            right,
-        } => {
-            let left = transpile_clause(*left, identifiers);
-            let right = transpile_clause(*right, identifiers);
-
-            output = match op_type {
-                ast::OperatorType::And => format!("({left}, {right})"),
-                ast::OperatorType::Or => format!("({left}; {right})"),
+        ast::Clause::Operator { op_type, clauses } => {
+            let separator = match op_type {
+                ast::OperatorType::And => ", ",
+                ast::OperatorType::Or => "; ",
             };
 
+            output += "(";
+            for (i, clause) in clauses.into_iter().enumerate() {
+                if i > 0 {
+                    output += separator;
+                }
+                output += &transpile_clause(clause, identifiers, scope);
+            }
+            output += ")";
+
             output
         }
         ast::Clause::Simple {
@@ -160,68 +620,100 @@ fn transpile_clause(clause: ast::Clause, identifiers: &mut Identifiers) -> Strin
                 output += r"\+"
             }
 
-            output += &format!("{}(", identifiers.get_or_create(&relationship));
-            output += &identifiers.get_or_create(&left);
-            if let Some(right) = right {
-                output += &format!(", {}", identifiers.get_or_create(&right))
-            }
-            output += ")";
+            output += &transpile_term(&relationship, &left, &right, identifiers, scope);
 
             output
         }
     }
 }
 
-/// Transpile a series of abstract syntax trees into a Prolog file.
-/// Returns the generated Prolog, the queries, and a map of Co-log identifiers to Prolog names.
-pub fn transpile(
+/// Transpile a series of abstract syntax trees into Prolog clauses, without their terminating full stops.
+/// Returns the clauses, one for each statement that isn't a query in the order of the statements, the queries, and a map of Co-log identifiers to Prolog names.
+pub fn transpile_clauses(
     trees: Vec<ast::Stmt>,
     initial_identifiers: Option<Identifiers>,
-) -> (String, Vec<Query>, Identifiers) {
+) -> (Vec<String>, Vec<Query>, Identifiers) {
     let mut identifiers = match initial_identifiers {
         Some(tmp) => tmp,
         None => Identifiers::new(),
     };
 
-    let mut output = String::from("style_check(-discontiguous).\neq(X, Y) :- X == Y.\n");
+    let mut clauses = Vec::new();
     let mut queries = Vec::new();
 
     for tree in trees {
+        let _enter = debug_span!("statement", kind = ?tree.kind()).entered();
+        let mut scope = Scope::new(identifiers.naming, &tree);
         match tree.kind() {
-            ast::StmtType::Query => {
-                let relationship = identifiers.get_or_create(tree.relationship()).to_string();
-                let left = identifiers.get_or_create(tree.left()).to_string();
-                let right = tree
-                    .right()
-                    .as_ref()
-                    .map(|iden| identifiers.get_or_create(iden));
-
-                queries.push(Query {
+            ast::StmtType::Query(kind) => {
+                let relationship = identifiers.get_or_create_relationship(tree.relationship());
+                let arguments = std::iter::once(tree.left())
+                    .chain(tree.right())
+                    .map(|iden| transpile_identifier(iden, &mut identifiers, &mut scope))
+                    .collect();
+
+                let query = Query {
+                    kind,
                     relationship,
-                    left,
-                    right,
-                })
+                    arguments,
+                    variables: scope.variables,
+                };
+                debug!(?query, "transpiled query");
+                queries.push(query)
             }
             _ => {
-                output += &format!("{}(", identifiers.get_or_create(tree.relationship()));
-                output += &identifiers.get_or_create(tree.left());
-                if let Some(right) = tree.right() {
-                    output += &format!(", {}", identifiers.get_or_create(right))
-                }
-                output += ")";
+                let mut clause = transpile_term(
+                    tree.relationship(),
+                    tree.left(),
+                    tree.right(),
+                    &mut identifiers,
+                    &mut scope,
+                );
 
                 if tree.kind() == ast::StmtType::Rule {
-                    output += " :- ";
-                    output +=
-                        &transpile_clause(tree.condition().clone().unwrap(), &mut identifiers);
+                    clause += " :- ";
+                    clause += &transpile_clause(
+                        tree.condition().clone().unwrap(),
+                        &mut identifiers,
+                        &mut scope,
+                    );
                 }
 
-                output += ".\n";
+                debug!(clause, "transpiled clause");
+                clauses.push(clause);
             }
         }
     }
 
-    (output, queries, identifiers)
+    (clauses, queries, identifiers)
+}
+
+/// Transpile a series of abstract syntax trees into a Prolog file, with one clause on each line after the prelude.
+/// Returns the generated Prolog, the queries, a map of Co-log identifiers to Prolog names, and a map of clauses to the statements they came from.
+pub fn transpile(
+    trees: Vec<ast::Stmt>,
+    initial_identifiers: Option<Identifiers>,
+) -> (String, Vec<Query>, Identifiers, SourceMap) {
+    let spans = trees
+        .iter()
+        .filter(|tree| !matches!(tree.kind(), ast::StmtType::Query(_)))
+        .map(ast::Stmt::span)
+        .collect();
+    let (clauses, queries, identifiers) = transpile_clauses(trees, initial_identifiers);
+
+    let mut output = String::from(PRELUDE);
+    for clause in &clauses {
+        output += clause;
+        output += ".\n";
+    }
+
+    let source_map = SourceMap {
+        first_line: PRELUDE.lines().count() + 1,
+        spans,
+        clauses,
+    };
+
+    (output, queries, identifiers, source_map)
 }
 
 #[cfg(test)]
@@ -0,0 +1,279 @@
+//! A reader for the standard syntax of Prolog clauses, used to check that the transpiler always generates valid Prolog.
+
+use std::{iter::Peekable, str::CharIndices};
+
+/// The associativity of an operator, in the notation used by Prolog's `op/3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpType {
+    Xfx,
+    Xfy,
+    Yfx,
+    Fy,
+    Fx,
+}
+
+/// The infix and prefix operators defined by ISO Prolog that the transpiler might use, with their priorities.
+const OPERATORS: &[(&str, u16, OpType)] = &[
+    (":-", 1200, OpType::Xfx),
+    (":-", 1200, OpType::Fx),
+    (";", 1100, OpType::Xfy),
+    ("->", 1050, OpType::Xfy),
+    (",", 1000, OpType::Xfy),
+    ("\\+", 900, OpType::Fy),
+    ("=", 700, OpType::Xfx),
+    ("\\=", 700, OpType::Xfx),
+    ("==", 700, OpType::Xfx),
+    ("\\==", 700, OpType::Xfx),
+    ("<", 700, OpType::Xfx),
+    (">", 700, OpType::Xfx),
+    ("=<", 700, OpType::Xfx),
+    (">=", 700, OpType::Xfx),
+    ("=:=", 700, OpType::Xfx),
+    ("=\\=", 700, OpType::Xfx),
+    ("is", 700, OpType::Xfx),
+    ("+", 500, OpType::Yfx),
+    ("-", 500, OpType::Yfx),
+    ("*", 400, OpType::Yfx),
+    ("/", 400, OpType::Yfx),
+    ("-", 200, OpType::Fy),
+];
+
+/// Characters that make up symbolic atoms such as `:-` and `\+`.
+const SYMBOL_CHARS: &str = "+-*/\\^<>=~:.?@#&$";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// An atom, and whether it was immediately followed by an opening parenthesis.
+    Atom(String, bool),
+    Variable,
+    Number,
+    Punctuation(char),
+    End,
+    Eof,
+}
+
+/// Splits a Prolog program into tokens.
+fn tokenise(program: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars: Peekable<CharIndices> = program.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '%' => {
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = chars.next_if(|&(_, c)| c.is_alphanumeric() || c == '_') {
+                    end = i + c.len_utf8();
+                }
+
+                if c.is_lowercase() {
+                    Token::Atom(program[start..end].to_string(), false)
+                } else {
+                    Token::Variable
+                }
+            }
+            c if c.is_ascii_digit() => {
+                while chars.next_if(|&(_, c)| c.is_ascii_digit()).is_some() {}
+                if chars.peek().map(|&(_, c)| c) == Some('.') {
+                    let mut lookahead = chars.clone();
+                    lookahead.next();
+                    if lookahead.peek().is_some_and(|&(_, c)| c.is_ascii_digit()) {
+                        chars.next();
+                        while chars.next_if(|&(_, c)| c.is_ascii_digit()).is_some() {}
+                    }
+                }
+                Token::Number
+            }
+            '\'' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) if chars.next_if(|&(_, c)| c == '\'').is_some() => {
+                            name.push('\'')
+                        }
+                        Some((_, '\'')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) => name.push(c),
+                            None => return Err("unterminated quoted atom".to_string()),
+                        },
+                        Some((_, c)) => name.push(c),
+                        None => return Err("unterminated quoted atom".to_string()),
+                    }
+                }
+                Token::Atom(name, false)
+            }
+            '(' | ')' | ',' | '|' | '[' | ']' => Token::Punctuation(c),
+            ';' | '!' => Token::Atom(c.to_string(), false),
+            // A full stop followed by whitespace or the end of the program ends a clause
+            '.' if chars
+                .peek()
+                .is_none_or(|&(_, c)| c.is_whitespace() || c == '%') =>
+            {
+                Token::End
+            }
+            c if SYMBOL_CHARS.contains(c) => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = chars.next_if(|&(_, c)| SYMBOL_CHARS.contains(c)) {
+                    end = i + c.len_utf8();
+                }
+                Token::Atom(program[start..end].to_string(), false)
+            }
+            c => return Err(format!("unexpected character '{c}'")),
+        };
+
+        // An atom directly followed by an opening parenthesis is the name of a compound term
+        let token = match token {
+            Token::Atom(name, _) if chars.peek().map(|&(_, c)| c) == Some('(') => {
+                Token::Atom(name, true)
+            }
+            token => token,
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Reads terms from a sequence of tokens, using the standard operator precedence parsing algorithm.
+struct Reader {
+    tokens: Vec<Token>,
+    current: usize,
+}
+
+impl Reader {
+    fn peek(&self) -> &Token {
+        self.tokens.get(self.current).unwrap_or(&Token::Eof)
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        self.current += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        let found = self.advance();
+        if found == token {
+            Ok(())
+        } else {
+            Err(format!("expected {token:?}, found {found:?}"))
+        }
+    }
+
+    /// The name of the next token if it could be an infix operator.
+    fn infix_name(&self) -> Option<String> {
+        match self.peek() {
+            Token::Atom(name, _) => Some(name.clone()),
+            Token::Punctuation(',') => Some(",".to_string()),
+            _ => None,
+        }
+    }
+
+    /// Reads a term whose priority is at most 'max'.
+    /// Returns the priority of the term that was read.
+    fn term(&mut self, max: u16) -> Result<u16, String> {
+        // The operators whose right operand is being read, with the maximum priority of the term each one is in.
+        // Keeping them on a stack, rather than reading each right operand recursively, means a long conjunction can't overflow the stack.
+        let mut operators = Vec::new();
+        let mut max = max;
+        let mut left = self.primary(max)?;
+
+        loop {
+            let operator = self.infix_name().and_then(|name| {
+                OPERATORS.iter().find(|&&(op, priority, kind)| {
+                    op == name && priority <= max && ![OpType::Fy, OpType::Fx].contains(&kind)
+                })
+            });
+            let operand = operator.and_then(|&(_, priority, kind)| {
+                let (left_max, right_max) = match kind {
+                    OpType::Xfx => (priority - 1, priority - 1),
+                    OpType::Xfy => (priority - 1, priority),
+                    _ => (priority, priority - 1),
+                };
+                (left <= left_max).then_some((priority, right_max))
+            });
+
+            match operand {
+                Some((priority, right_max)) => {
+                    self.advance();
+                    operators.push((max, priority));
+                    max = right_max;
+                    left = self.primary(max)?;
+                }
+                None => match operators.pop() {
+                    Some((outer_max, priority)) => {
+                        max = outer_max;
+                        left = priority;
+                    }
+                    None => return Ok(left),
+                },
+            }
+        }
+    }
+
+    /// Reads a term that doesn't start with an infix operator.
+    fn primary(&mut self, max: u16) -> Result<u16, String> {
+        match self.advance() {
+            Token::Variable | Token::Number => Ok(0),
+            Token::Punctuation('(') => {
+                self.term(1200)?;
+                self.expect(Token::Punctuation(')'))?;
+                Ok(0)
+            }
+            Token::Atom(_, true) => {
+                self.expect(Token::Punctuation('('))?;
+                self.term(999)?;
+                while self.peek() == &Token::Punctuation(',') {
+                    self.advance();
+                    self.term(999)?;
+                }
+                self.expect(Token::Punctuation(')'))?;
+                Ok(0)
+            }
+            Token::Atom(name, false) => {
+                let prefix = OPERATORS
+                    .iter()
+                    .find(|&&(op, _, kind)| op == name && [OpType::Fy, OpType::Fx].contains(&kind));
+
+                // A prefix operator followed by something that can't start a term is just an atom
+                let starts_term = matches!(
+                    self.peek(),
+                    Token::Atom(..) | Token::Variable | Token::Number | Token::Punctuation('(')
+                );
+                match prefix {
+                    Some(&(_, priority, kind)) if starts_term && priority <= max => {
+                        let argument_max = if kind == OpType::Fy {
+                            priority
+                        } else {
+                            priority - 1
+                        };
+                        self.term(argument_max)?;
+                        Ok(priority)
+                    }
+                    _ => Ok(0),
+                }
+            }
+            token => Err(format!("expected a term, found {token:?}")),
+        }
+    }
+}
+
+/// Checks that a program is a valid sequence of Prolog clauses.
+/// Returns a description of the first problem found if it isn't.
+pub fn check_prolog(program: &str) -> Result<(), String> {
+    let mut reader = Reader {
+        tokens: tokenise(program)?,
+        current: 0,
+    };
+
+    while reader.current < reader.tokens.len() {
+        reader.term(1200)?;
+        reader.expect(Token::End)?;
+    }
+
+    Ok(())
+}
@@ -1,11 +1,30 @@
-use super::{Identifier, Identifiers, Query};
-use crate::{parser, scanner};
+use proptest::{prelude::*, sample::Index};
+
+use super::{syntax, Identifier, IdentifierError, Identifiers, Naming, Query, PRELUDE};
+use crate::{
+    parser::{
+        self,
+        ast::{self, QueryType},
+        Location,
+    },
+    scanner, TranspileError,
+};
 
 fn transpile(source: &str) -> (String, Vec<Query>, Identifiers) {
-    super::transpile(
+    let (prolog, queries, identifiers, _) = super::transpile(
         parser::parse(&scanner::scan(source).unwrap()).unwrap(),
         None,
-    )
+    );
+    (prolog, queries, identifiers)
+}
+
+fn scoped(cl_name: &str, pl_name: &str) -> Identifier {
+    Identifier {
+        cl_name: cl_name.to_string(),
+        pl_name: pl_name.to_string(),
+        article: None,
+        preposition: None,
+    }
 }
 
 #[test]
@@ -13,32 +32,53 @@ fn fact_unary() {
     assert_eq!(
         transpile("A hamster is a mammal."),
         (
-            "style_check(-discontiguous).\neq(X, Y) :- X == Y.\nl1(l2).\n".to_string(),
+            PRELUDE.to_string() + "l1(l2).\n",
             vec![],
-            Identifiers {
-                identifiers: vec![
-                    Identifier {
-                        cl_name: "eq".to_string(),
-                        pl_name: "eq".to_string(),
-                        article: None,
-                        preposition: None
-                    },
-                    Identifier {
-                        cl_name: "mammal".to_string(),
-                        pl_name: "l1".to_string(),
-                        article: Some("a".to_string(),),
-                        preposition: None,
-                    },
-                    Identifier {
-                        cl_name: "hamster".to_string(),
-                        pl_name: "l2".to_string(),
-                        article: Some("A".to_string(),),
-                        preposition: None,
-                    },
-                ],
-                highest_literal: 2,
-                highest_variable: 0,
-            },
+            Identifiers::try_from(vec![
+                Identifier {
+                    cl_name: "eq".to_string(),
+                    pl_name: "eq".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "mammal".to_string(),
+                    pl_name: "l1".to_string(),
+                    article: Some("a".to_string(),),
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "hamster".to_string(),
+                    pl_name: "l2".to_string(),
+                    article: Some("A".to_string(),),
+                    preposition: None,
+                },
+            ])
+            .unwrap(),
         )
     )
 }
@@ -48,38 +88,59 @@ fn fact_binary() {
     assert_eq!(
         transpile("John is the brother of Jack."),
         (
-            "style_check(-discontiguous).\neq(X, Y) :- X == Y.\nl1(l2, l3).\n".to_string(),
+            PRELUDE.to_string() + "l1(l2, l3).\n",
             vec![],
-            Identifiers {
-                identifiers: vec![
-                    Identifier {
-                        cl_name: "eq".to_string(),
-                        pl_name: "eq".to_string(),
-                        article: None,
-                        preposition: None
-                    },
-                    Identifier {
-                        cl_name: "brother".to_string(),
-                        pl_name: "l1".to_string(),
-                        article: Some("the".to_string(),),
-                        preposition: Some("of".to_string(),),
-                    },
-                    Identifier {
-                        cl_name: "john".to_string(),
-                        pl_name: "l2".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                    Identifier {
-                        cl_name: "jack".to_string(),
-                        pl_name: "l3".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                ],
-                highest_literal: 3,
-                highest_variable: 0,
-            },
+            Identifiers::try_from(vec![
+                Identifier {
+                    cl_name: "eq".to_string(),
+                    pl_name: "eq".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "brother".to_string(),
+                    pl_name: "l1".to_string(),
+                    article: Some("the".to_string(),),
+                    preposition: Some("of".to_string(),),
+                },
+                Identifier {
+                    cl_name: "john".to_string(),
+                    pl_name: "l2".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "jack".to_string(),
+                    pl_name: "l3".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+            ])
+            .unwrap(),
         )
     )
 }
@@ -89,56 +150,71 @@ fn rule_unary() {
     assert_eq!(
         transpile("X is a mammal if X is an animal and X is warm-blooded."),
         (
-            "style_check(-discontiguous).\neq(X, Y) :- X == Y.\nl1(V1) :- (l2(V1), l3(V1)).\n"
-                .to_string(),
+            PRELUDE.to_string() + "l1(V1) :- (l2(V1), l3(V1)).\n",
             vec![],
-            Identifiers {
-                identifiers: vec![
-                    Identifier {
-                        cl_name: "eq".to_string(),
-                        pl_name: "eq".to_string(),
-                        article: None,
-                        preposition: None
-                    },
-                    Identifier {
-                        cl_name: "mammal".to_string(),
-                        pl_name: "l1".to_string(),
-                        article: Some("a".to_string(),),
-                        preposition: None,
-                    },
-                    Identifier {
-                        cl_name: "X".to_string(),
-                        pl_name: "V1".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                    Identifier {
-                        cl_name: "animal".to_string(),
-                        pl_name: "l2".to_string(),
-                        article: Some("an".to_string(),),
-                        preposition: None,
-                    },
-                    Identifier {
-                        cl_name: "warm-blooded".to_string(),
-                        pl_name: "l3".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                ],
-                highest_literal: 3,
-                highest_variable: 1,
-            },
+            Identifiers::try_from(vec![
+                Identifier {
+                    cl_name: "eq".to_string(),
+                    pl_name: "eq".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "mammal".to_string(),
+                    pl_name: "l1".to_string(),
+                    article: Some("a".to_string(),),
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "animal".to_string(),
+                    pl_name: "l2".to_string(),
+                    article: Some("an".to_string(),),
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "warm-blooded".to_string(),
+                    pl_name: "l3".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+            ])
+            .unwrap(),
         )
     )
 }
 
 #[test]
 fn rule_binary() {
-    assert_eq!(transpile("X is the brother of Y if X is the sibling of Y and X is male."), (
-        "style_check(-discontiguous).\neq(X, Y) :- X == Y.\nl1(V1, V2) :- (l2(V1, V2), l3(V1)).\n".to_string(),
-        vec![],
-        Identifiers {
-            identifiers: vec![
+    assert_eq!(
+        transpile("X is the brother of Y if X is the sibling of Y and X is male."),
+        (
+            PRELUDE.to_string() + "l1(V1, V2) :- (l2(V1, V2), l3(V1)).\n",
+            vec![],
+            Identifiers::try_from(vec![
                 Identifier {
                     cl_name: "eq".to_string(),
                     pl_name: "eq".to_string(),
@@ -146,36 +222,40 @@ fn rule_binary() {
                     preposition: None
                 },
                 Identifier {
-                    cl_name: "brother".to_string(),
-                    pl_name: "l1".to_string(),
-                    article: Some(
-                        "the".to_string(),
-                    ),
-                    preposition: Some(
-                        "of".to_string(),
-                    ),
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None
                 },
                 Identifier {
-                    cl_name: "X".to_string(),
-                    pl_name: "V1".to_string(),
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
                     article: None,
-                    preposition: None,
+                    preposition: None
                 },
                 Identifier {
-                    cl_name: "Y".to_string(),
-                    pl_name: "V2".to_string(),
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
                     article: None,
-                    preposition: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "brother".to_string(),
+                    pl_name: "l1".to_string(),
+                    article: Some("the".to_string(),),
+                    preposition: Some("of".to_string(),),
                 },
                 Identifier {
                     cl_name: "sibling".to_string(),
                     pl_name: "l2".to_string(),
-                    article: Some(
-                        "the".to_string(),
-                    ),
-                    preposition: Some(
-                        "of".to_string(),
-                    ),
+                    article: Some("the".to_string(),),
+                    preposition: Some("of".to_string(),),
                 },
                 Identifier {
                     cl_name: "male".to_string(),
@@ -183,20 +263,20 @@ fn rule_binary() {
                     article: None,
                     preposition: None,
                 },
-            ],
-            highest_literal: 3,
-            highest_variable: 2,
-        },
-    ))
+            ])
+            .unwrap(),
+        )
+    )
 }
 
 #[test]
 fn rule_binary_parentheses() {
-    assert_eq!(transpile("B is thing of C if (B is one and C is one) or (B is two and C is two)."), (
-        "style_check(-discontiguous).\neq(X, Y) :- X == Y.\nl1(V1, V2) :- ((l2(V1), l2(V2)); (l3(V1), l3(V2))).\n".to_string(),
-        vec![],
-        Identifiers {
-            identifiers: vec![
+    assert_eq!(
+        transpile("B is thing of C if (B is one and C is one) or (B is two and C is two)."),
+        (
+            PRELUDE.to_string() + "l1(V1, V2) :- ((l2(V1), l2(V2)); (l3(V1), l3(V2))).\n",
+            vec![],
+            Identifiers::try_from(vec![
                 Identifier {
                     cl_name: "eq".to_string(),
                     pl_name: "eq".to_string(),
@@ -204,24 +284,34 @@ fn rule_binary_parentheses() {
                     preposition: None
                 },
                 Identifier {
-                    cl_name: "thing".to_string(),
-                    pl_name: "l1".to_string(),
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
                     article: None,
-                    preposition: Some(
-                        "of".to_string(),
-                    ),
+                    preposition: None
                 },
                 Identifier {
-                    cl_name: "B".to_string(),
-                    pl_name: "V1".to_string(),
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
                     article: None,
-                    preposition: None,
+                    preposition: None
                 },
                 Identifier {
-                    cl_name: "C".to_string(),
-                    pl_name: "V2".to_string(),
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
                     article: None,
-                    preposition: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "thing".to_string(),
+                    pl_name: "l1".to_string(),
+                    article: None,
+                    preposition: Some("of".to_string(),),
                 },
                 Identifier {
                     cl_name: "one".to_string(),
@@ -235,26 +325,48 @@ fn rule_binary_parentheses() {
                     article: None,
                     preposition: None,
                 },
-            ],
-            highest_literal: 3,
-            highest_variable: 2,
-        },
-    ))
+            ])
+            .unwrap(),
+        )
+    )
 }
 
 #[test]
 fn rule_binary_negation() {
     assert_eq!(transpile("X is the sibling of Y if Z is the parent of X and Z is the parent of Y and X is not Y."), (
-        "style_check(-discontiguous).\neq(X, Y) :- X == Y.\nl1(V1, V2) :- (l2(V3, V1), (l2(V3, V2), \\+eq(V1, V2))).\n".to_string(),
+        PRELUDE.to_string() + "l1(V1, V2) :- (l2(V3, V1), l2(V3, V2), \\+eq(V1, V2)).\n",
         vec![],
-        Identifiers {
-            identifiers: vec![
+        Identifiers::try_from(vec![
                 Identifier {
                     cl_name: "eq".to_string(),
                     pl_name: "eq".to_string(),
                     article: None,
                     preposition: None
                 },
+                Identifier {
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None
+                },
                 Identifier {
                     cl_name: "sibling".to_string(),
                     pl_name: "l1".to_string(),
@@ -265,18 +377,6 @@ fn rule_binary_negation() {
                         "of".to_string(),
                     ),
                 },
-                Identifier {
-                    cl_name: "X".to_string(),
-                    pl_name: "V1".to_string(),
-                    article: None,
-                    preposition: None,
-                },
-                Identifier {
-                    cl_name: "Y".to_string(),
-                    pl_name: "V2".to_string(),
-                    article: None,
-                    preposition: None,
-                },
                 Identifier {
                     cl_name: "parent".to_string(),
                     pl_name: "l2".to_string(),
@@ -287,16 +387,8 @@ fn rule_binary_negation() {
                         "of".to_string(),
                     ),
                 },
-                Identifier {
-                    cl_name: "Z".to_string(),
-                    pl_name: "V3".to_string(),
-                    article: None,
-                    preposition: None,
-                },
-            ],
-            highest_literal: 2,
-            highest_variable: 3,
-        },
+            ])
+        .unwrap(),
     ))
 }
 
@@ -305,36 +397,58 @@ fn query_literal() {
     assert_eq!(
         transpile("Is a hamster a mammal?"),
         (
-            "style_check(-discontiguous).\neq(X, Y) :- X == Y.\n".to_string(),
+            PRELUDE.to_string(),
             vec![Query {
+                kind: QueryType::Solutions,
                 relationship: "l1".to_string(),
-                left: "l2".to_string(),
-                right: None
+                arguments: vec!["l2".to_string()],
+                variables: vec![]
             }],
-            Identifiers {
-                identifiers: vec![
-                    Identifier {
-                        cl_name: "eq".to_string(),
-                        pl_name: "eq".to_string(),
-                        article: None,
-                        preposition: None
-                    },
-                    Identifier {
-                        cl_name: "mammal".to_string(),
-                        pl_name: "l1".to_string(),
-                        article: Some("a".to_string(),),
-                        preposition: None,
-                    },
-                    Identifier {
-                        cl_name: "hamster".to_string(),
-                        pl_name: "l2".to_string(),
-                        article: Some("a".to_string(),),
-                        preposition: None,
-                    },
-                ],
-                highest_literal: 2,
-                highest_variable: 0,
-            },
+            Identifiers::try_from(vec![
+                Identifier {
+                    cl_name: "eq".to_string(),
+                    pl_name: "eq".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "mammal".to_string(),
+                    pl_name: "l1".to_string(),
+                    article: Some("a".to_string(),),
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "hamster".to_string(),
+                    pl_name: "l2".to_string(),
+                    article: Some("a".to_string(),),
+                    preposition: None,
+                },
+            ])
+            .unwrap(),
         )
     )
 }
@@ -344,132 +458,186 @@ fn query_literal_literal() {
     assert_eq!(
         transpile("Is John the brother of Jack?"),
         (
-            "style_check(-discontiguous).\neq(X, Y) :- X == Y.\n".to_string(),
-            vec![Query {
-                left: "l2".to_string(),
-                relationship: "l1".to_string(),
-                right: Some("l3".to_string())
-            }],
-            Identifiers {
-                identifiers: vec![
-                    Identifier {
-                        cl_name: "eq".to_string(),
-                        pl_name: "eq".to_string(),
-                        article: None,
-                        preposition: None
-                    },
-                    Identifier {
-                        cl_name: "brother".to_string(),
-                        pl_name: "l1".to_string(),
-                        article: Some("the".to_string(),),
-                        preposition: Some("of".to_string(),),
-                    },
-                    Identifier {
-                        cl_name: "john".to_string(),
-                        pl_name: "l2".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                    Identifier {
-                        cl_name: "jack".to_string(),
-                        pl_name: "l3".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                ],
-                highest_literal: 3,
-                highest_variable: 0,
-            },
-        )
-    )
-}
-
-#[test]
-fn query_literal_pronoun() {
-    assert_eq!(
-        transpile("John is the brother of who?"),
-        (
-            "style_check(-discontiguous).\neq(X, Y) :- X == Y.\n".to_string(),
+            PRELUDE.to_string(),
             vec![Query {
-                left: "l2".to_string(),
+                kind: QueryType::Solutions,
                 relationship: "l1".to_string(),
-                right: Some("V1".to_string())
+                arguments: vec!["l2".to_string(), "l3".to_string()],
+                variables: vec![]
             }],
-            Identifiers {
-                identifiers: vec![
-                    Identifier {
-                        cl_name: "eq".to_string(),
-                        pl_name: "eq".to_string(),
-                        article: None,
-                        preposition: None
-                    },
-                    Identifier {
-                        cl_name: "brother".to_string(),
-                        pl_name: "l1".to_string(),
-                        article: Some("the".to_string(),),
-                        preposition: Some("of".to_string(),),
-                    },
-                    Identifier {
-                        cl_name: "john".to_string(),
-                        pl_name: "l2".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                    Identifier {
-                        cl_name: "who".to_string(),
-                        pl_name: "V1".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                ],
-                highest_literal: 2,
-                highest_variable: 1,
-            },
-        )
-    )
-}
-
-#[test]
+            Identifiers::try_from(vec![
+                Identifier {
+                    cl_name: "eq".to_string(),
+                    pl_name: "eq".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "brother".to_string(),
+                    pl_name: "l1".to_string(),
+                    article: Some("the".to_string(),),
+                    preposition: Some("of".to_string(),),
+                },
+                Identifier {
+                    cl_name: "john".to_string(),
+                    pl_name: "l2".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "jack".to_string(),
+                    pl_name: "l3".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+            ])
+            .unwrap(),
+        )
+    )
+}
+
+#[test]
+fn query_literal_pronoun() {
+    assert_eq!(
+        transpile("John is the brother of who?"),
+        (
+            PRELUDE.to_string(),
+            vec![Query {
+                kind: QueryType::Solutions,
+                relationship: "l1".to_string(),
+                arguments: vec!["l2".to_string(), "V1".to_string()],
+                variables: vec![scoped("who", "V1")]
+            }],
+            Identifiers::try_from(vec![
+                Identifier {
+                    cl_name: "eq".to_string(),
+                    pl_name: "eq".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "brother".to_string(),
+                    pl_name: "l1".to_string(),
+                    article: Some("the".to_string(),),
+                    preposition: Some("of".to_string(),),
+                },
+                Identifier {
+                    cl_name: "john".to_string(),
+                    pl_name: "l2".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+            ])
+            .unwrap(),
+        )
+    )
+}
+
+#[test]
 fn query_pronoun_literal() {
     assert_eq!(
         transpile("Who is the brother of Jane?"),
         (
-            "style_check(-discontiguous).\neq(X, Y) :- X == Y.\n".to_string(),
+            PRELUDE.to_string(),
             vec![Query {
-                left: "V1".to_string(),
+                kind: QueryType::Solutions,
                 relationship: "l1".to_string(),
-                right: Some("l2".to_string())
+                arguments: vec!["V1".to_string(), "l2".to_string()],
+                variables: vec![scoped("who", "V1")]
             }],
-            Identifiers {
-                identifiers: vec![
-                    Identifier {
-                        cl_name: "eq".to_string(),
-                        pl_name: "eq".to_string(),
-                        article: None,
-                        preposition: None
-                    },
-                    Identifier {
-                        cl_name: "brother".to_string(),
-                        pl_name: "l1".to_string(),
-                        article: Some("the".to_string(),),
-                        preposition: Some("of".to_string(),),
-                    },
-                    Identifier {
-                        cl_name: "who".to_string(),
-                        pl_name: "V1".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                    Identifier {
-                        cl_name: "jane".to_string(),
-                        pl_name: "l2".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                ],
-                highest_literal: 2,
-                highest_variable: 1,
-            },
+            Identifiers::try_from(vec![
+                Identifier {
+                    cl_name: "eq".to_string(),
+                    pl_name: "eq".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "brother".to_string(),
+                    pl_name: "l1".to_string(),
+                    article: Some("the".to_string(),),
+                    preposition: Some("of".to_string(),),
+                },
+                Identifier {
+                    cl_name: "jane".to_string(),
+                    pl_name: "l2".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+            ])
+            .unwrap(),
         )
     )
 }
@@ -479,42 +647,52 @@ fn query_pronoun_pronoun() {
     assert_eq!(
         transpile("Who is the sister of who?"),
         (
-            "style_check(-discontiguous).\neq(X, Y) :- X == Y.\n".to_string(),
+            PRELUDE.to_string(),
             vec![Query {
-                left: "V1".to_string(),
+                kind: QueryType::Solutions,
                 relationship: "l1".to_string(),
-                right: Some("V2".to_string())
+                arguments: vec!["V1".to_string(), "V2".to_string()],
+                variables: vec![scoped("who", "V1"), scoped("who_2", "V2")]
             }],
-            Identifiers {
-                identifiers: vec![
-                    Identifier {
-                        cl_name: "eq".to_string(),
-                        pl_name: "eq".to_string(),
-                        article: None,
-                        preposition: None
-                    },
-                    Identifier {
-                        cl_name: "sister".to_string(),
-                        pl_name: "l1".to_string(),
-                        article: Some("the".to_string(),),
-                        preposition: Some("of".to_string(),),
-                    },
-                    Identifier {
-                        cl_name: "who".to_string(),
-                        pl_name: "V1".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                    Identifier {
-                        cl_name: "who".to_string(),
-                        pl_name: "V2".to_string(),
-                        article: None,
-                        preposition: None,
-                    },
-                ],
-                highest_literal: 1,
-                highest_variable: 2,
-            },
+            Identifiers::try_from(vec![
+                Identifier {
+                    cl_name: "eq".to_string(),
+                    pl_name: "eq".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None
+                },
+                Identifier {
+                    cl_name: "sister".to_string(),
+                    pl_name: "l1".to_string(),
+                    article: Some("the".to_string(),),
+                    preposition: Some("of".to_string(),),
+                },
+            ])
+            .unwrap(),
         )
     )
 }
@@ -522,16 +700,39 @@ fn query_pronoun_pronoun() {
 #[test]
 fn program_1() {
     assert_eq!(transpile("A hamster is an animal. A hamster is warm-blooded. X is a mammal if X is an animal and X is warm-blooded."), (
-        "style_check(-discontiguous).\neq(X, Y) :- X == Y.\nl1(l2).\nl3(l2).\nl4(V1) :- (l1(V1), l3(V1)).\n".to_string(),
+        PRELUDE.to_string() + "l1(l2).\nl3(l2).\nl4(V1) :- (l1(V1), l3(V1)).\n",
         vec![],
-        Identifiers {
-            identifiers: vec![
+        Identifiers::try_from(vec![
                 Identifier {
                     cl_name: "eq".to_string(),
                     pl_name: "eq".to_string(),
                     article: None,
                     preposition: None,
                 },
+                Identifier {
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None,
+                },
                 Identifier {
                     cl_name: "animal".to_string(),
                     pl_name: "l1".to_string(),
@@ -562,32 +763,47 @@ fn program_1() {
                     ),
                     preposition: None,
                 },
-                Identifier {
-                    cl_name: "X".to_string(),
-                    pl_name: "V1".to_string(),
-                    article: None,
-                    preposition: None,
-                },
-            ],
-            highest_literal: 4,
-            highest_variable: 1,
-        },
+            ])
+        .unwrap(),
     ))
 }
 
 #[test]
 fn program_2() {
     assert_eq!(transpile("John is the parent of Jack. John is the parent of Jane. X is the sibling of Y if Z is the parent of X and Z is the parent of Y and X is not Y."), (
-        "style_check(-discontiguous).\neq(X, Y) :- X == Y.\nl1(l2, l3).\nl1(l2, l4).\nl5(V1, V2) :- (l1(V3, V1), (l1(V3, V2), \\+eq(V1, V2))).\n".to_string(),
+        PRELUDE.to_string() + "l1(l2, l3).\nl1(l2, l4).\nl5(V1, V2) :- (l1(V3, V1), l1(V3, V2), \\+eq(V1, V2)).\n",
         vec![],
-        Identifiers {
-            identifiers: vec![
+        Identifiers::try_from(vec![
                 Identifier {
                     cl_name: "eq".to_string(),
                     pl_name: "eq".to_string(),
                     article: None,
                     preposition: None,
                 },
+                Identifier {
+                    cl_name: "gt".to_string(),
+                    pl_name: "gt".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "lt".to_string(),
+                    pl_name: "lt".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "ge".to_string(),
+                    pl_name: "ge".to_string(),
+                    article: None,
+                    preposition: None,
+                },
+                Identifier {
+                    cl_name: "le".to_string(),
+                    pl_name: "le".to_string(),
+                    article: None,
+                    preposition: None,
+                },
                 Identifier {
                     cl_name: "parent".to_string(),
                     pl_name: "l1".to_string(),
@@ -626,27 +842,705 @@ fn program_2() {
                         "of".to_string(),
                     ),
                 },
-                Identifier {
-                    cl_name: "X".to_string(),
-                    pl_name: "V1".to_string(),
-                    article: None,
-                    preposition: None,
-                },
-                Identifier {
-                    cl_name: "Y".to_string(),
-                    pl_name: "V2".to_string(),
-                    article: None,
-                    preposition: None,
-                },
-                Identifier {
-                    cl_name: "Z".to_string(),
-                    pl_name: "V3".to_string(),
-                    article: None,
-                    preposition: None,
-                },
-            ],
-            highest_literal: 5,
-            highest_variable: 3,
-        },
+            ])
+        .unwrap(),
     ))
 }
+
+#[test]
+fn identifier_from_pl_name() {
+    let (_, _, identifiers) = transpile("John is the brother of Jack.");
+
+    assert_eq!(
+        identifiers
+            .get_from_pl_name("l3")
+            .map(|identifier| identifier.cl_name()),
+        Some("jack")
+    );
+    assert_eq!(identifiers.get_from_pl_name("l4"), None);
+}
+
+#[test]
+fn identifier_from_cl_name() {
+    let (_, _, identifiers) = transpile("John is the brother of Jack.");
+
+    // Names are found whatever the case they are written in
+    assert_eq!(
+        identifiers
+            .get_from_cl_name("JOHN")
+            .map(|identifier| identifier.pl_name()),
+        Some("l2")
+    );
+    assert_eq!(identifiers.get_from_cl_name("jill"), None);
+}
+
+#[test]
+fn unicode_identifiers() {
+    let (prolog, queries, identifiers) = transpile(
+        "Zoë is the sister of José. ÉÀ is a sister of Zoë if Zoë is the sister of ÉÀ. Is ÉÀ a sister of Zoë?",
+    );
+
+    assert_eq!(
+        prolog,
+        PRELUDE.to_string() + "l1(l2, l3).\nl1(V1, l2) :- l1(l2, V1).\n"
+    );
+    assert_eq!(
+        identifiers
+            .get_from_pl_name("l2")
+            .map(|identifier| identifier.cl_name()),
+        Some("zoë")
+    );
+    assert_eq!(queries[0].get_variable("V1"), Some("ÉÀ"));
+    assert_eq!(identifiers.get_from_pl_name("V1"), None);
+}
+
+#[test]
+fn multi_word_identifiers() {
+    let (prolog, _, identifiers) =
+        transpile("New York is the capital of New York State. \"new york\" is a city.");
+
+    assert_eq!(prolog, PRELUDE.to_string() + "l1(l2, l3).\nl4(l2).\n");
+    assert_eq!(
+        identifiers
+            .get_from_pl_name("l3")
+            .map(|identifier| identifier.cl_name()),
+        Some("new york state")
+    );
+}
+
+#[test]
+fn variable_scopes() {
+    let (prolog, queries, identifiers) = transpile(
+        "X is a mammal if X is a hamster. Y is a pet if Y is a hamster. X is a friend of Y if Y is a pet of X. Who is a pet of X?",
+    );
+
+    assert_eq!(
+        prolog,
+        PRELUDE.to_string() + "l1(V1) :- l2(V1).\nl3(V1) :- l2(V1).\nl4(V1, V2) :- l3(V2, V1).\n"
+    );
+    assert_eq!(
+        queries,
+        vec![Query {
+            kind: QueryType::Solutions,
+            relationship: "l3".to_string(),
+            arguments: vec!["V1".to_string(), "V2".to_string()],
+            variables: vec![scoped("who", "V1"), scoped("X", "V2")],
+        }]
+    );
+    assert!(identifiers
+        .identifiers()
+        .iter()
+        .all(|identifier| !identifier.pl_name().starts_with('V')));
+}
+
+#[test]
+fn readable_names() {
+    let (prolog, queries, _, _) = super::transpile(
+        parser::parse(
+            &scanner::scan(
+                "John is the brother of Jack. Jack is warm-blooded. Jack is \"warm blooded\". \
+            X is a sibling of Y if X is the brother of Y. Alice is a member of the club. \
+            All hamsters are mammals. Every parent of X is an ancestor of X. Who is a sibling of who?",
+            )
+            .unwrap(),
+        )
+        .unwrap(),
+        Some(Identifiers::with_naming(Naming::Readable)),
+    );
+
+    assert_eq!(
+        prolog,
+        PRELUDE.to_string()
+            + "co_brother(john, jack).\nco_warm_blooded(jack).\nco_warm_blooded_2(jack).\n\
+            co_sibling(X, Y) :- co_brother(X, Y).\nco_member(alice, club).\nco_mammal(X) :- co_hamster(X).\n\
+            co_ancestor(X_2, X) :- co_parent(X_2, X).\n"
+    );
+    assert_eq!(
+        queries,
+        vec![Query {
+            kind: QueryType::Solutions,
+            relationship: "co_sibling".to_string(),
+            arguments: vec!["Who".to_string(), "Who_2".to_string()],
+            variables: vec![scoped("who", "Who"), scoped("who_2", "Who_2")],
+        }]
+    );
+}
+
+#[test]
+fn readable_names_avoid_builtins() {
+    let (prolog, _, _, _) = super::transpile(
+        parser::parse_source(
+            "Bob is the name of Dog. X is open if X is the name of Y and X is greater than 3.",
+        )
+        .unwrap(),
+        Some(Identifiers::with_naming(Naming::Readable)),
+    );
+
+    assert_eq!(
+        prolog,
+        PRELUDE.to_string() + "co_name(bob, dog).\nco_open(X) :- (co_name(X, Y), gt(X, 3)).\n"
+    );
+}
+
+#[test]
+fn source_map() {
+    let source = "Alice likes Bob. Who likes Bob?\nX admires Y if X likes Y.";
+    let (prolog, _, _, source_map) = super::transpile(parser::parse_source(source).unwrap(), None);
+    let last_line = prolog.lines().count();
+
+    assert_eq!(
+        source_map.statement_at(last_line).map(|span| &source[span]),
+        Some("X admires Y if X likes Y.")
+    );
+    assert_eq!(
+        source_map
+            .statement_at(last_line - 1)
+            .map(|span| &source[span]),
+        Some("Alice likes Bob.")
+    );
+    assert_eq!(source_map.statement_at(1), None);
+    assert_eq!(source_map.statement_at(last_line + 1), None);
+    assert_eq!(
+        source_map
+            .clauses()
+            .map(|(span, clause)| (&source[span], clause))
+            .collect::<Vec<_>>(),
+        [
+            ("Alice likes Bob.", "l1(l2, l3)"),
+            ("X admires Y if X likes Y.", "l4(V1, V2) :- l1(V1, V2)"),
+        ]
+    );
+}
+
+#[test]
+fn identifiers_try_from() {
+    let (_, _, identifiers) = transpile("John is the brother of Jack.");
+    let table = identifiers.identifiers().clone();
+    assert_eq!(Identifiers::try_from(table.clone()), Ok(identifiers));
+
+    // New literals are numbered after the highest number in the table, whatever order it is in
+    let mut reordered = table.clone();
+    reordered[5..].reverse();
+    let (prolog, _, _, _) = super::transpile(
+        parser::parse_source("Jane is nice.").unwrap(),
+        Some(Identifiers::try_from(reordered).unwrap()),
+    );
+    assert!(prolog.ends_with("\nl4(l5).\n"));
+
+    let invalid = [
+        (
+            scoped("X", "V1"),
+            IdentifierError::InvalidName("V1".to_string()),
+        ),
+        (
+            scoped("jane", "l1"),
+            IdentifierError::DuplicatePlName("l1".to_string()),
+        ),
+        (
+            scoped("john", "l9"),
+            IdentifierError::DuplicateClName("john".to_string()),
+        ),
+        (
+            scoped("jill", "7"),
+            IdentifierError::Number("7".to_string()),
+        ),
+        (scoped("7", "l9"), IdentifierError::Number("7".to_string())),
+        (
+            scoped("Jill", "l9"),
+            IdentifierError::UppercaseClName("Jill".to_string()),
+        ),
+    ];
+    for (identifier, err) in invalid {
+        let mut invalid_table = table.clone();
+        invalid_table.push(identifier);
+        assert_eq!(Identifiers::try_from(invalid_table), Err(err));
+    }
+    assert_eq!(
+        Identifiers::try_from(table[1..].to_vec()),
+        Err(IdentifierError::Builtin("eq".to_string()))
+    );
+    // Numbers are named the same in Co-log and Prolog
+    let (_, _, identifiers) = transpile("Alice is 34 years old.");
+    assert_eq!(
+        Identifiers::try_from(identifiers.identifiers().clone()),
+        Ok(identifiers)
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn save_and_load() {
+    let (_, _, identifiers, _) = super::transpile(
+        parser::parse_source("John is the brother of Jack.").unwrap(),
+        Some(Identifiers::with_naming(Naming::Readable)),
+    );
+
+    let mut saved = Vec::new();
+    identifiers.save(&mut saved).unwrap();
+    let loaded = Identifiers::load(saved.as_slice()).unwrap();
+    assert_eq!(loaded, identifiers);
+
+    let (prolog, _, _, _) = super::transpile(
+        parser::parse_source("Jane is the sister of John.").unwrap(),
+        Some(loaded),
+    );
+    assert!(prolog.ends_with("\nco_sister(jane, john).\n"));
+
+    let damaged = String::from_utf8(saved)
+        .unwrap()
+        .replace("\"jack\"", "\"Jack\"");
+    let err = Identifiers::load(damaged.as_bytes()).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("'Jack' is not a valid Prolog name"));
+}
+
+#[test]
+fn comparisons() {
+    let (prolog, _, identifiers) =
+        transpile("Alice is 34 years old. X is an adult if X is N years old and N is at least 18.");
+
+    assert_eq!(
+        prolog,
+        PRELUDE.to_string() + "l1(l2, 34).\nl3(V1) :- (l1(V1, V2), ge(V2, 18)).\n"
+    );
+    assert_eq!(
+        identifiers
+            .get_from_pl_name("34")
+            .map(|identifier| identifier.cl_name()),
+        Some("34")
+    );
+
+    let (prolog, queries, _) =
+        transpile("X is an adult if the age of X is greater than 17. Is 34 greater than 17?");
+    assert_eq!(
+        prolog,
+        PRELUDE.to_string() + "l1(V1) :- (l2(V2, V1), gt(V2, 17)).\n"
+    );
+    assert_eq!(queries[0].relationship, "gt");
+    assert_eq!(
+        queries[0].arguments,
+        vec!["34".to_string(), "17".to_string()]
+    );
+}
+
+#[test]
+fn n_ary() {
+    let (prolog, queries, _) =
+        transpile("X is the sum of Y and Z if X is the total of Y and Z. Is 5 the sum of 2 and 3?");
+
+    assert_eq!(
+        prolog,
+        PRELUDE.to_string() + "l1(V1, V2, V3) :- l2(V1, V2, V3).\n"
+    );
+    assert_eq!(
+        queries,
+        vec![Query {
+            kind: QueryType::Solutions,
+            relationship: "l1".to_string(),
+            arguments: vec!["5".to_string(), "2".to_string(), "3".to_string()],
+            variables: vec![]
+        }]
+    );
+}
+
+#[test]
+fn actions() {
+    let (prolog, queries, _) =
+        transpile("Alice likes Bob. X admires Y if X likes Y and Y is famous. Who likes Bob?");
+
+    assert_eq!(
+        prolog,
+        PRELUDE.to_string() + "l1(l2, l3).\nl4(V1, V2) :- (l1(V1, V2), l5(V2)).\n"
+    );
+    assert_eq!(
+        queries,
+        vec![Query {
+            kind: QueryType::Solutions,
+            relationship: "l1".to_string(),
+            arguments: vec!["V1".to_string(), "l3".to_string()],
+            variables: vec![scoped("who", "V1")]
+        }]
+    );
+}
+
+#[test]
+fn quantified() {
+    let (prolog, queries, _) = transpile(
+        "All hamsters are mammals. Every parent of X is an ancestor of X. Is Sam a mammal?",
+    );
+
+    assert_eq!(
+        prolog,
+        PRELUDE.to_string() + "l1(V1) :- l2(V1).\nl3(V1, V2) :- l4(V1, V2).\n"
+    );
+    assert_eq!(queries[0].relationship, "l1");
+}
+
+#[test]
+fn only_plural_nouns_singular() {
+    let (prolog, queries, _) = transpile(
+        "Tom is the boss of the news. Tom is new. Alice has a cat. How many bosses does the news have?",
+    );
+
+    // Only the noun counted by the query is a plural, so the literal 'news' isn't the relationship 'new'
+    assert_eq!(
+        prolog,
+        PRELUDE.to_string() + "l1(l2, l3).\nl4(l2).\nl5(l6, l7).\n"
+    );
+    assert_eq!(queries[0].relationship, "l1");
+}
+
+#[test]
+fn query_types() {
+    let (_, queries, _) = transpile(
+        "Alice is the parent of Bob. Is there a parent of Bob? How many children does Alice have?",
+    );
+
+    assert_eq!(
+        queries,
+        vec![
+            Query {
+                kind: QueryType::Exists,
+                relationship: "l1".to_string(),
+                arguments: vec!["V1".to_string(), "l3".to_string()],
+                variables: vec![scoped("there", "V1")]
+            },
+            Query {
+                kind: QueryType::Count,
+                relationship: "l4".to_string(),
+                arguments: vec!["V1".to_string(), "l2".to_string()],
+                variables: vec![scoped("how many", "V1")]
+            },
+        ]
+    );
+}
+
+#[test]
+fn check_prolog() {
+    assert_eq!(
+        syntax::check_prolog(
+            ":- style_check(-discontiguous).\neq(X, Y) :- X == Y.\nl1(V1) :- (l2(V1); \\+l3(V1, l4)).\n"
+        ),
+        Ok(())
+    );
+    assert!(syntax::check_prolog("l1(V1) :- .\n").is_err());
+    assert!(syntax::check_prolog("l1(V1, ).\n").is_err());
+    assert!(syntax::check_prolog("l1((V1).\n").is_err());
+    assert!(syntax::check_prolog("l1(V1)\n").is_err());
+}
+
+#[test]
+fn transpile_long_chain() {
+    let (prolog, _, _) = transpile(&format!(
+        "X is bad if X is a thing{}.",
+        " or X is bad".repeat(10_000)
+    ));
+
+    assert!(prolog.ends_with(&format!("(l2(V1){}).\n", "; l1(V1)".repeat(10_000))));
+    assert_eq!(syntax::check_prolog(&prolog), Ok(()));
+}
+
+#[test]
+fn transpile_unstratifiable() {
+    let source = "Ann is one.\nX is odd if X is one and X is not odd.";
+
+    match crate::transpile(source.to_string(), None) {
+        Err(TranspileError::Unstratifiable(err)) => {
+            assert_eq!(err.relationship(), "odd");
+            assert_eq!(
+                err.locations().collect::<Vec<_>>(),
+                vec![Location { line: 2, column: 1 }]
+            );
+        }
+        result => panic!("expected a stratification error, found {result:?}"),
+    }
+}
+
+/// Words that can't be used as identifiers, and the names of the built in comparisons.
+const RESERVED: [&str; 29] = [
+    "a", "an", "the", "and", "or", "of", "to", "from", "with", "by", "for", "in", "on", "is",
+    "are", "if", "who", "what", "not", "all", "every", "each", "how", "there", "eq", "gt", "lt",
+    "ge", "le",
+];
+fn literal() -> impl Strategy<Value = String> {
+    "[a-z]{1,8}".prop_filter("reserved word", |word| {
+        !RESERVED.contains(&word.as_str()) && !RESERVED.contains(&ast::singular(word).as_str())
+    })
+}
+
+fn variable() -> impl Strategy<Value = String> {
+    "[A-Z]{1,3}".prop_filter("reserved word", |word| {
+        !RESERVED.contains(&word.to_lowercase().as_str())
+    })
+}
+
+fn article() -> impl Strategy<Value = &'static str> {
+    prop_oneof![Just("a"), Just("an"), Just("the")]
+}
+
+fn proper_name() -> impl Strategy<Value = String> {
+    "[A-Z][a-z]{1,6}( [A-Z][a-z]{1,6}){0,2}".prop_filter("reserved word", |name| {
+        name.split(' ')
+            .all(|word| !RESERVED.contains(&word.to_lowercase().as_str()))
+    })
+}
+
+fn quoted_name() -> impl Strategy<Value = String> {
+    r#"[a-zA-Z .?"\\]{0,10}"#
+        .prop_filter("reserved word", |name| {
+            let name = name.to_lowercase();
+            !RESERVED.contains(&name.as_str()) && !RESERVED.contains(&ast::singular(&name).as_str())
+        })
+        .prop_map(|name| format!("\"{}\"", name.replace('\\', r"\\").replace('"', r#"\""#)))
+}
+
+fn number() -> impl Strategy<Value = String> {
+    "[0-9]{1,3}(\\.[0-9]{1,2})?"
+}
+
+fn identifier() -> impl Strategy<Value = String> {
+    prop_oneof![
+        variable(),
+        literal(),
+        number(),
+        proper_name(),
+        quoted_name(),
+        (article(), literal()).prop_map(|(article, literal)| format!("{article} {literal}")),
+        Just("who".to_string()),
+    ]
+    .boxed()
+}
+
+/// Generates a measure, such as `34 years old`.
+fn measure() -> impl Strategy<Value = String> {
+    (
+        prop_oneof![number(), variable()],
+        proptest::collection::vec(literal(), 1..3),
+    )
+        .prop_map(|(value, words)| format!("{value} {}", words.join(" ")))
+}
+
+fn preposition() -> impl Strategy<Value = &'static str> {
+    prop_oneof![Just("of"), Just("to"), Just("from"), Just("with")]
+}
+
+/// Generates the identifiers after the first right hand identifier of a relationship, such as ` to Bob and Carol`.
+/// An 'and' only joins arguments if 'joins' is true, as it does after 'the', so otherwise it is replaced with a preposition.
+fn more_arguments(joins: bool) -> impl Strategy<Value = String> {
+    proptest::collection::vec(
+        (prop_oneof![preposition(), Just("and")], identifier()),
+        0..3,
+    )
+    .prop_map(move |arguments| {
+        arguments
+            .into_iter()
+            .map(|(separator, identifier)| match separator {
+                "and" if !joins => format!(" with {identifier}"),
+                _ => format!(" {separator} {identifier}"),
+            })
+            .collect()
+    })
+}
+
+/// Generates the identifiers listed after the last argument of a relationship, such as ` and Carol`.
+fn others() -> impl Strategy<Value = String> {
+    proptest::collection::vec(identifier(), 1..3).prop_map(|others| {
+        others
+            .into_iter()
+            .map(|other| format!(" and {other}"))
+            .collect()
+    })
+}
+
+fn relationship() -> impl Strategy<Value = String> {
+    let relationship = proptest::option::of(article())
+        .prop_flat_map(|article| {
+            let joins = article == Some("the");
+            (
+                Just(article),
+                literal(),
+                proptest::option::of((preposition(), identifier(), more_arguments(joins))),
+            )
+        })
+        .prop_map(|(article, literal, right)| {
+            let mut relationship = literal;
+            if let Some(article) = article {
+                relationship = format!("{article} {relationship}");
+            }
+            if let Some((preposition, right, more)) = right {
+                relationship = format!("{relationship} {preposition} {right}{more}");
+            }
+            relationship
+        });
+
+    prop_oneof![4 => relationship, 1 => measure()].boxed()
+}
+
+/// Generates an action, such as `gave the book to Bob`.
+fn action() -> impl Strategy<Value = String> {
+    (
+        literal(),
+        prop_oneof![
+            Just(String::new()),
+            (identifier(), more_arguments(false))
+                .prop_map(|(object, more)| format!(" {object}{more}")),
+            (preposition(), identifier(), more_arguments(false))
+                .prop_map(|(preposition, right, more)| format!(" {preposition} {right}{more}")),
+        ],
+    )
+        .prop_map(|(verb, arguments)| format!("{verb}{arguments}"))
+        .boxed()
+}
+
+/// Generates everything in a statement or clause after its left hand identifier, such as `is the brother of Jack` or `likes Bob`.
+fn predicate() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => relationship().prop_map(|relationship| format!("is {relationship}")),
+        1 => action(),
+    ]
+}
+
+/// Generates the phrase of a comparison, such as `greater than`.
+fn comparison() -> impl Strategy<Value = &'static str> {
+    prop_oneof![
+        Just("greater than"),
+        Just("less than"),
+        Just("at least"),
+        Just("at most")
+    ]
+}
+
+fn clause() -> impl Strategy<Value = String> {
+    let not = prop_oneof![Just(""), Just(" not")];
+    let simple = prop_oneof![
+        (identifier(), not.clone(), relationship())
+            .prop_map(|(left, not, relationship)| format!("{left} is{not} {relationship}")),
+        (identifier(), action()).prop_map(|(left, action)| format!("{left} {action}")),
+        (identifier(), literal(), identifier(), others())
+            .prop_map(|(left, verb, object, others)| format!("{left} {verb} {object}{others}")),
+        (variable(), not.clone(), prop_oneof![variable(), number()])
+            .prop_map(|(left, not, right)| format!("{left} is{not} {right}")),
+        (identifier(), not.clone(), comparison(), identifier()).prop_map(
+            |(left, not, comparison, right)| format!("{left} is{not} {comparison} {right}")
+        ),
+        (literal(), identifier(), not, comparison(), identifier()).prop_map(
+            |(literal, argument, not, comparison, value)| format!(
+                "the {literal} of {argument} is{not} {comparison} {value}"
+            )
+        ),
+    ];
+
+    simple.prop_recursive(3, 16, 2, |inner| {
+        prop_oneof![
+            (
+                inner.clone(),
+                prop_oneof![Just("and"), Just("or")],
+                inner.clone()
+            )
+                .prop_map(|(left, operator, right)| format!("{left} {operator} {right}")),
+            inner.prop_map(|clause| format!("({clause})")),
+        ]
+    })
+}
+
+fn statement() -> impl Strategy<Value = String> {
+    prop_oneof![
+        (identifier(), predicate()).prop_map(|(left, predicate)| format!("{left} {predicate}.")),
+        (identifier(), literal(), identifier(), others())
+            .prop_map(|(left, verb, object, others)| format!("{left} {verb} {object}{others}.")),
+        (identifier(), predicate(), clause())
+            .prop_map(|(left, predicate, condition)| format!("{left} {predicate} if {condition}.")),
+        (identifier(), predicate()).prop_map(|(left, predicate)| format!("{left} {predicate}?")),
+        (
+            prop_oneof![Just("All"), Just("every"), Just("Each")],
+            literal(),
+            // An 'and' followed by the verb would end the noun's arguments
+            proptest::collection::vec((preposition(), identifier()), 0..3),
+            predicate()
+        )
+            .prop_map(|(quantifier, noun, right, predicate)| {
+                let right: String = right
+                    .into_iter()
+                    .map(|(preposition, right)| format!(" {preposition} {right}"))
+                    .collect();
+                format!("{quantifier} {noun}{right} {predicate}.")
+            }),
+        (identifier(), relationship())
+            .prop_map(|(left, relationship)| format!("Is {left} {relationship}?")),
+        relationship().prop_map(|relationship| format!("Is there {relationship}?")),
+        predicate().prop_map(|predicate| format!("Is there anyone who {predicate}?")),
+        (literal(), proptest::option::of(identifier())).prop_map(|(noun, owner)| match owner {
+            Some(owner) => format!("How many {noun} does {owner} have?"),
+            None => format!("How many {noun} are there?"),
+        }),
+        (
+            literal(),
+            proptest::option::of((preposition(), identifier(), more_arguments(false)))
+        )
+            .prop_map(|(noun, right)| match right {
+                Some((preposition, right, more)) => {
+                    format!("List all {noun} {preposition} {right}{more}.")
+                }
+                None => format!("List all {noun}."),
+            }),
+    ]
+    .boxed()
+}
+
+/// Generates valid Co-log programs.
+fn program() -> impl Strategy<Value = String> {
+    proptest::collection::vec(statement(), 0..8).prop_map(|statements| statements.join("\n"))
+}
+
+proptest! {
+    #[test]
+    fn transpile_valid_program(source in program()) {
+        let result = crate::transpile(source, None);
+        // Random negations can make a relationship depend on its own negation
+        prop_assume!(!matches!(result, Err(TranspileError::Unstratifiable(_))));
+        let (prolog, _, _, _) = result.unwrap();
+        prop_assert_eq!(syntax::check_prolog(&prolog), Ok(()), "{}", prolog);
+    }
+
+    #[test]
+    fn transpile_valid_program_readable(source in program()) {
+        let result = crate::transpile(source, Some(Identifiers::with_naming(Naming::Readable)));
+        prop_assume!(!matches!(result, Err(TranspileError::Unstratifiable(_))));
+        let (prolog, _, _, _) = result.unwrap();
+        prop_assert_eq!(syntax::check_prolog(&prolog), Ok(()), "{}", prolog);
+    }
+
+    #[test]
+    fn display_round_trip(source in program()) {
+        let trees = parser::parse(&scanner::scan(&source).unwrap()).unwrap();
+        let displayed = trees
+            .iter()
+            .map(|tree| tree.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        prop_assert_eq!(parser::parse(&scanner::scan(&displayed).unwrap()).unwrap(), trees);
+    }
+
+    #[test]
+    fn transpile_never_panics(source in "\\PC{0,64}") {
+        let _ = crate::transpile(source.clone(), None);
+        let _ = crate::check(&source);
+    }
+
+    #[test]
+    fn transpile_damaged_program_never_panics(
+        source in program(),
+        start in any::<Index>(),
+        length in 0..16usize,
+    ) {
+        // Cut a piece out of a valid program, which is more likely to reach deep into the parser than random text
+        let start = start.index(source.len() + 1);
+        let end = (start + length).min(source.len());
+        let damaged = format!("{}{}", &source[..start], &source[end..]);
+
+        let _ = crate::transpile(damaged.clone(), None);
+        let _ = crate::check(&damaged);
+    }
+}